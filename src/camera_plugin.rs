@@ -5,13 +5,14 @@ use bevy::core_pipeline::tonemapping::Tonemapping;
 use bevy::input::mouse::AccumulatedMouseScroll;
 use bevy::prelude::*;
 
-use crate::player::player_plugin::Player;
+use crate::player::{lifecycle_plugin::PlayerSpawned, player_plugin::Player};
 
 pub struct CameraPlugin;
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, setup_camera)
-            .add_systems(Update, update_camera);
+            .add_systems(Update, update_camera)
+            .add_observer(snap_camera_to_player);
     }
 }
 
@@ -30,17 +31,42 @@ fn setup_camera(mut commands: Commands) {
     ));
 }
 
-/// Update the camera position by tracking the player.
+/// Jump to a freshly spawned player instead of panning all the way from
+/// where it died
+fn snap_camera_to_player(
+    trigger: Trigger<PlayerSpawned>,
+    mut camera_query: Query<&mut Transform, (With<Camera2d>, Without<Player>)>,
+    player_pos: Query<&Transform, Without<Camera2d>>,
+) {
+    let (Ok(mut cam_transform), Ok(player_transform)) =
+        (camera_query.single_mut(), player_pos.get(trigger.player))
+    else {
+        return;
+    };
+    cam_transform.translation.x = player_transform.translation.x;
+    cam_transform.translation.y = player_transform.translation.y;
+}
+
+/// Update the camera position by tracking the player, or the last place it
+/// was seen while it is dead or not spawned yet.
 fn update_camera(
     mut camera_query: Query<&mut Transform, (With<Camera2d>, Without<Player>)>,
     mouse_scroll: Res<AccumulatedMouseScroll>,
     player_pos: Query<&Transform, (With<Player>, Without<Camera2d>)>,
+    mut last_player_position: Local<Option<Vec3>>,
     time: Res<Time>,
 ) {
-    let mut cam_transform = camera_query.single_mut().unwrap();
-    let player_transform = player_pos.single().unwrap();
+    let Ok(mut cam_transform) = camera_query.single_mut() else {
+        return;
+    };
+    if let Ok(player_transform) = player_pos.single() {
+        *last_player_position = Some(player_transform.translation);
+    }
+    // the player is spawned once its blueprint is loaded
+    let Some(Vec3 { x, y, .. }) = *last_player_position else {
+        return;
+    };
 
-    let Vec3 { x, y, .. } = player_transform.translation;
     let direction = Vec3::new(x, y, cam_transform.translation.z);
 
     // Applies a smooth effect to camera movement using stable interpolation
@@ -0,0 +1,78 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::asset::{io::Reader, LoadContext};
+use serde::{de::DeserializeOwned, Serialize};
+use thiserror::Error;
+
+/// Any failure reading or writing one of the RON files of the game: configs,
+/// saves, exports and assets
+#[derive(Debug, Error)]
+pub enum RonFileError {
+    #[error("could not access {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("could not write {}: {source}", path.display())]
+    Serialize { path: PathBuf, source: ron::Error },
+    #[error("could not parse {}: {source}", path.display())]
+    Deserialize {
+        path: PathBuf,
+        source: ron::error::SpannedError,
+    },
+}
+
+pub fn read_ron<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T, RonFileError> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path).map_err(|source| RonFileError::Io {
+        path: path.into(),
+        source,
+    })?;
+    ron::from_str(&text).map_err(|source| RonFileError::Deserialize {
+        path: path.into(),
+        source,
+    })
+}
+
+/// Write `value` pretty printed, creating the missing directories
+pub fn write_ron<T: Serialize>(path: impl AsRef<Path>, value: &T) -> Result<(), RonFileError> {
+    let path = path.as_ref();
+    let io_error = |source: std::io::Error| RonFileError::Io {
+        path: path.into(),
+        source,
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(io_error)?;
+    }
+    let text =
+        ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default()).map_err(|source| {
+            RonFileError::Serialize {
+                path: path.into(),
+                source,
+            }
+        })?;
+    fs::write(path, text).map_err(io_error)
+}
+
+/// [`read_ron`] for an `AssetLoader`
+pub async fn read_ron_asset<T: DeserializeOwned>(
+    reader: &mut dyn Reader,
+    load_context: &LoadContext<'_>,
+) -> Result<T, RonFileError> {
+    let path = load_context.path();
+    let mut bytes = Vec::new();
+    reader
+        .read_to_end(&mut bytes)
+        .await
+        .map_err(|source| RonFileError::Io {
+            path: path.into(),
+            source,
+        })?;
+    ron::de::from_bytes(&bytes).map_err(|source| RonFileError::Deserialize {
+        path: path.into(),
+        source,
+    })
+}
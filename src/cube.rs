@@ -2,6 +2,17 @@
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 
+use crate::robot_constructor::{Shape, ShapePart};
+
+/// Registers the [`ShapePalette`] used by the SpawnCube click mode
+pub struct CubePlugin;
+impl Plugin for CubePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ShapePalette>();
+    }
+}
+
+/// `width` and `height` are the full size of the shape bounding box
 #[derive(Component)]
 pub struct Cube {
     pub width: f32,
@@ -9,9 +20,23 @@ pub struct Cube {
 }
 
 impl Cube {
+    /// A cube shaped as a `width` x `height` rectangle
     pub fn bundle(width: f32, height: f32) -> impl Bundle {
+        Self::with_shape(Shape::Rectangle {
+            width: width / 2.0,
+            height: height / 2.0,
+        })
+    }
+
+    /// A cube of any shape, its size is taken from the shape
+    pub fn with_shape(shape: Shape) -> impl Bundle {
+        let size = shape.size();
         (
-            Self { width, height },
+            Self {
+                width: size.x,
+                height: size.y,
+            },
+            shape,
             // physics
             RigidBody::Dynamic,
             //TransformInterpolation,
@@ -21,3 +46,144 @@ impl Cube {
         )
     }
 }
+
+/// The shapes SpawnCube can spawn, cycled from the UI
+#[derive(Resource)]
+pub struct ShapePalette {
+    pub shapes: Vec<(&'static str, Shape)>,
+    pub current: usize,
+}
+
+impl Default for ShapePalette {
+    fn default() -> Self {
+        Self {
+            shapes: vec![
+                (
+                    "Square",
+                    Shape::Rectangle {
+                        width: 15.0,
+                        height: 15.0,
+                    },
+                ),
+                (
+                    "Plank",
+                    Shape::Rectangle {
+                        width: 40.0,
+                        height: 6.0,
+                    },
+                ),
+                (
+                    "Pillar",
+                    Shape::Rectangle {
+                        width: 8.0,
+                        height: 30.0,
+                    },
+                ),
+                ("Ball", Shape::Circle { radius: 15.0 }),
+                ("Big ball", Shape::Circle { radius: 25.0 }),
+                (
+                    "Capsule",
+                    Shape::Capsule {
+                        half_length: 15.0,
+                        radius: 8.0,
+                    },
+                ),
+                (
+                    "Triangle",
+                    Shape::Triangle {
+                        a: Vec2::new(-18.0, -15.0),
+                        b: Vec2::new(18.0, -15.0),
+                        c: Vec2::new(0.0, 18.0),
+                    },
+                ),
+                (
+                    "Pentagon",
+                    Shape::RegularPolygon {
+                        sides: 5,
+                        radius: 18.0,
+                    },
+                ),
+                (
+                    "Hexagon",
+                    Shape::RegularPolygon {
+                        sides: 6,
+                        radius: 18.0,
+                    },
+                ),
+                (
+                    "Gem",
+                    Shape::ConvexPolygon(vec![
+                        Vec2::new(0.0, -22.0),
+                        Vec2::new(16.0, 4.0),
+                        Vec2::new(9.0, 14.0),
+                        Vec2::new(-9.0, 14.0),
+                        Vec2::new(-16.0, 4.0),
+                    ]),
+                ),
+                (
+                    "L-piece",
+                    Shape::Compound(vec![
+                        ShapePart {
+                            shape: Shape::Rectangle {
+                                width: 30.0,
+                                height: 8.0,
+                            },
+                            offset: Vec2::ZERO,
+                            rotation: 0.0,
+                        },
+                        ShapePart {
+                            shape: Shape::Rectangle {
+                                width: 8.0,
+                                height: 20.0,
+                            },
+                            offset: Vec2::new(-22.0, 28.0),
+                            rotation: 0.0,
+                        },
+                    ]),
+                ),
+                ("Wheel", wheel(25.0, 6)),
+            ],
+            current: 0,
+        }
+    }
+}
+
+impl ShapePalette {
+    pub fn name(&self) -> &'static str {
+        self.shapes[self.current].0
+    }
+
+    pub fn shape(&self) -> Shape {
+        self.shapes[self.current].1.clone()
+    }
+
+    pub fn next(&mut self) -> &'static str {
+        self.current = (self.current + 1) % self.shapes.len();
+        self.name()
+    }
+}
+
+/// A rim of `2 * spokes` planks crossed at the center by `spokes` planks
+fn wheel(radius: f32, spokes: u32) -> Shape {
+    let step = std::f32::consts::PI / spokes as f32;
+    let rim = (0..spokes * 2).map(|i| {
+        let angle = step * i as f32;
+        ShapePart {
+            shape: Shape::Rectangle {
+                width: radius * (step / 2.0).tan(),
+                height: 3.0,
+            },
+            offset: Vec2::from_angle(angle).perp() * radius,
+            rotation: angle,
+        }
+    });
+    let spokes = (0..spokes).map(|i| ShapePart {
+        shape: Shape::Rectangle {
+            width: radius,
+            height: 2.0,
+        },
+        offset: Vec2::ZERO,
+        rotation: step * i as f32,
+    });
+    Shape::Compound(rim.chain(spokes).collect())
+}
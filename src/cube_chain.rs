@@ -1,8 +1,315 @@
-use bevy::prelude::*;
+use bevy::{color::palettes::css::ORANGE, prelude::*};
+use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
 
+use crate::{
+    cube::Cube,
+    input::Action,
+    joint::{spawn_joint, JointKind, JointSpec},
+    player::{
+        player_plugin::{ClickMode, Player},
+        resources::PlayerMouseCoor,
+    },
+    robot_constructor::{EntityColor, Shape},
+};
+
+/// ClickMode::BuildChain: drag with the left button to spawn a chain of
+/// cubes between the press and release points, ToggleChainPin toggles
+/// pinning its end
+pub struct CubeChainPlugin;
+impl Plugin for CubeChainPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ChainTool>()
+            .add_observer(despawn_segment_pins)
+            .add_systems(Update, (toggle_chain_end_pin, drag_cube_chain));
+    }
+}
+
+/// Marks the cubes of a chain spawned by a [`CubeChainBuilder`]
 #[derive(Component)]
 pub struct CubeChain;
 
-pub fn spawn_cube_chain(mut commands: Commands) {
-    commands.spawn(CubeChain);
+/// A fixed body holding an end of a chain in place, despawned with the
+/// `segment` it holds
+#[derive(Component)]
+pub struct ChainPin {
+    pub segment: Entity,
+}
+
+/// Spawn a [`ChainPin`] at `position` and the joint holding `segment` to it,
+/// returns the pin
+pub fn spawn_chain_pin(
+    commands: &mut Commands,
+    position: Vec2,
+    segment: Entity,
+    spec: JointSpec,
+) -> Entity {
+    let pin = commands
+        .spawn((
+            ChainPin { segment },
+            RigidBody::Fixed,
+            Transform::from_translation(position.extend(0.0)),
+        ))
+        .id();
+    spawn_joint(commands, pin, segment, spec);
+    pin
+}
+
+/// Describes a chain between `start` and `end`, as many segments as fit are
+/// spawned and the gaps between them stretched so that the chain spans
+/// exactly from one point to the other.
+///
+/// ```ron
+/// (start: (-200.0, 0.0), end: (200.0, 0.0), segment_length: 30.0, stiffness: Some(50.0), pin_start: true, pin_end: true)
+/// ```
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CubeChainBuilder {
+    pub start: Vec2,
+    pub end: Vec2,
+    /// Size of a segment along the chain
+    pub segment_length: f32,
+    pub segment_thickness: f32,
+    /// Minimum gap between two segments
+    pub spacing: f32,
+    /// `None` links the segments with revolute joints, `Some` with springs
+    /// of that stiffness
+    pub stiffness: Option<f32>,
+    pub damping: f32,
+    /// `JointSpec::max_force` of every link
+    pub max_force: Option<f32>,
+    /// Hold the start in place
+    pub pin_start: bool,
+    /// Hold the end in place, a bridge has both ends pinned and a rope only one
+    pub pin_end: bool,
+    pub color: Color,
+}
+
+impl Default for CubeChainBuilder {
+    fn default() -> Self {
+        Self {
+            start: Vec2::ZERO,
+            end: Vec2::X * 200.0,
+            segment_length: 30.0,
+            segment_thickness: 10.0,
+            spacing: 4.0,
+            stiffness: None,
+            damping: 0.5,
+            max_force: None,
+            pin_start: true,
+            pin_end: false,
+            color: ORANGE.into(),
+        }
+    }
+}
+
+impl CubeChainBuilder {
+    /// Number of segments, their length and the gap between them. A lone
+    /// segment is stretched over the whole chain so that both pins sit on
+    /// its ends
+    fn layout(&self) -> (usize, f32, f32) {
+        let length = self.start.distance(self.end);
+        let segment = self.segment_length.max(1.0);
+        let spacing = self.spacing.max(0.0);
+        let count = ((length + spacing) / (segment + spacing)).floor().max(1.0) as usize;
+        if count == 1 {
+            return (1, length.max(1.0), 0.0);
+        }
+        let gap = (length - count as f32 * segment) / (count - 1) as f32;
+        (count, segment, gap)
+    }
+
+    /// The joint between two segments of length `segment`, `gap` apart
+    fn link(&self, segment: f32, gap: f32) -> JointSpec {
+        let half_length = segment / 2.0;
+        let (kind, offset) = match self.stiffness {
+            // the anchors of two neighbours meet in the middle of the gap
+            None => (
+                JointKind::Revolute {
+                    limits: None,
+                    motor: None,
+                },
+                gap / 2.0,
+            ),
+            // a spring pulls the segment ends `gap` apart
+            Some(stiffness) => (
+                JointKind::Spring {
+                    rest_length: gap,
+                    stiffness,
+                    damping: self.damping,
+                },
+                0.0,
+            ),
+        };
+        JointSpec::new(
+            kind,
+            Vec2::X * (half_length + offset),
+            Vec2::NEG_X * (half_length + offset),
+        )
+        .with_max_force(self.max_force)
+    }
+
+    /// Spawn the segments, the joints between them and the pins, returns
+    /// the segments from the start to the end
+    pub fn spawn(&self, commands: &mut Commands) -> Vec<Entity> {
+        let direction = (self.end - self.start).normalize_or(Vec2::X);
+        let rotation = Quat::from_rotation_z(direction.to_angle());
+        let (count, segment, gap) = self.layout();
+        let half_length = segment / 2.0;
+
+        let links: Vec<Entity> = (0..count)
+            .map(|i| {
+                let center =
+                    self.start + direction * (half_length + i as f32 * (2.0 * half_length + gap));
+                commands
+                    .spawn(Cube::with_shape(Shape::Rectangle {
+                        width: half_length,
+                        height: self.segment_thickness / 2.0,
+                    }))
+                    .insert((
+                        CubeChain,
+                        EntityColor(self.color),
+                        Transform::from_translation(center.extend(0.0)).with_rotation(rotation),
+                        Velocity::zero(),
+                    ))
+                    .id()
+            })
+            .collect();
+
+        let link = self.link(segment, gap);
+        for pair in links.windows(2) {
+            spawn_joint(commands, pair[0], pair[1], link);
+        }
+
+        let pins = [
+            (self.pin_start, self.start, links[0], -half_length),
+            (self.pin_end, self.end, links[count - 1], half_length),
+        ];
+        for (pinned, position, segment, anchor) in pins {
+            if !pinned {
+                continue;
+            }
+            let spec = JointSpec::new(
+                JointKind::Revolute {
+                    limits: None,
+                    motor: None,
+                },
+                Vec2::ZERO,
+                Vec2::X * anchor,
+            )
+            .with_max_force(self.max_force);
+            spawn_chain_pin(commands, position, segment, spec);
+        }
+
+        links
+    }
+}
+
+/// A pin left alone would keep an invisible fixed body in the world
+fn despawn_segment_pins(
+    trigger: Trigger<OnRemove, Cube>,
+    pins: Query<(Entity, &ChainPin)>,
+    mut commands: Commands,
+) {
+    pins.iter()
+        .filter(|(_, pin)| pin.segment == trigger.target())
+        .for_each(|(entity, _)| commands.entity(entity).try_despawn());
+}
+
+/// The chain spawned by dragging in BuildChain mode
+#[derive(Resource, Default)]
+pub struct ChainTool {
+    /// `start` and `end` are replaced by the drag
+    pub preset: CubeChainBuilder,
+    drag_start: Option<Vec2>,
+}
+
+fn toggle_chain_end_pin(
+    actions: Res<ButtonInput<Action>>,
+    mut tool: ResMut<ChainTool>,
+    player: Single<&Player>,
+) {
+    if player.0 == ClickMode::BuildChain && actions.just_pressed(Action::ToggleChainPin) {
+        tool.preset.pin_end = !tool.preset.pin_end;
+        info!("chain end pinned: {}", tool.preset.pin_end);
+    }
+}
+
+/// Press starts the chain, release spawns it, a line previews it meanwhile
+fn drag_cube_chain(
+    mut commands: Commands,
+    mut tool: ResMut<ChainTool>,
+    mouse_coor: Res<PlayerMouseCoor>,
+    actions: Res<ButtonInput<Action>>,
+    player: Single<&Player>,
+    mut gizmos: Gizmos,
+) {
+    if player.0 != ClickMode::BuildChain {
+        tool.drag_start = None;
+        return;
+    }
+    let cursor: Vec2 = mouse_coor.into_inner().into();
+    if actions.just_pressed(Action::SpawnCube) {
+        tool.drag_start = Some(cursor);
+    }
+    let Some(start) = tool.drag_start else {
+        return;
+    };
+    if actions.pressed(Action::SpawnCube) {
+        gizmos.line_2d(start, cursor, tool.preset.color);
+        return;
+    }
+    tool.drag_start = None;
+    if start.distance(cursor) >= tool.preset.segment_length {
+        CubeChainBuilder {
+            start,
+            end: cursor,
+            ..tool.preset.clone()
+        }
+        .spawn(&mut commands);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chain(length: f32, spacing: f32) -> CubeChainBuilder {
+        CubeChainBuilder {
+            start: Vec2::ZERO,
+            end: Vec2::X * length,
+            segment_length: 30.0,
+            spacing,
+            ..default()
+        }
+    }
+
+    /// The segments and gaps must span exactly from start to end
+    fn span((count, segment, gap): (usize, f32, f32)) -> f32 {
+        count as f32 * segment + (count - 1) as f32 * gap
+    }
+
+    #[test]
+    fn layout_spans_the_chain() {
+        for length in [30.0, 64.0, 100.0, 250.0, 1000.0] {
+            let layout = chain(length, 4.0).layout();
+            assert!(layout.0 > 1 || length < 64.0);
+            assert!(layout.2 >= 4.0 || layout.0 == 1);
+            assert!((span(layout) - length).abs() < 1e-3, "{length}: {layout:?}");
+        }
+    }
+
+    #[test]
+    fn layout_stretches_a_lone_segment() {
+        let (count, segment, gap) = chain(20.0, 4.0).layout();
+        assert_eq!((count, segment, gap), (1, 20.0, 0.0));
+    }
+
+    #[test]
+    fn layout_clamps_negative_spacing() {
+        let layout = chain(100.0, -40.0).layout();
+        assert_eq!(layout.0, 3);
+        assert!(layout.2 >= 0.0);
+        assert!((span(layout) - 100.0).abs() < 1e-3);
+    }
 }
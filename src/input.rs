@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+
+use bevy::{input::InputSystem, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::config::read_ron;
+
+/// Where the player bindings are read from at startup
+const INPUT_MAP_PATH: &str = "config/input.ron";
+
+/// Maps keyboard, mouse and gamepad buttons to [`Action`]s, player systems
+/// read `Res<ButtonInput<Action>>` instead of raw keys
+pub struct ActionInputPlugin;
+impl Plugin for ActionInputPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputMap>()
+            .init_resource::<ButtonInput<Action>>()
+            .init_resource::<GamepadSticks>()
+            .add_systems(Startup, load_input_map)
+            .add_systems(PreUpdate, update_actions.after(InputSystem));
+    }
+}
+
+/// Everything the player can do, whatever device it comes from
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    /// Next player form
+    ChangeShape,
+    /// Previous player form
+    PreviousShape,
+    SpawnCube,
+    DeleteCube,
+    ToggleClickMode,
+    /// Only in platformer mode
+    Jump,
+    /// Switch between flight and platformer movement
+    ToggleMovementMode,
+    /// Dig a crater in the terrain under the cursor
+    Dig,
+    /// Drop the bodies selected in JoinCube
+    CancelSelection,
+    /// Remove the joint selected in EditJoint
+    DeleteJoint,
+    /// Scale the selected joint length up
+    JointLonger,
+    JointShorter,
+    /// Raise the selected joint stiffness or motor velocity
+    JointStronger,
+    JointWeaker,
+    /// Make the selected joint breakable or indestructible
+    ToggleJointBreakable,
+    /// Scale the break force of the selected joint up
+    JointForceUp,
+    JointForceDown,
+    SaveContraption,
+    LoadContraption,
+    /// Export the tiles painted in PaintTerrain
+    ExportTiles,
+    /// Pin or free the end of the chains built in BuildChain
+    ToggleChainPin,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    /// A button of any connected gamepad
+    Gamepad(GamepadButton),
+}
+
+/// Sticks of every connected gamepad added together, each at most 1 long
+#[derive(Resource, Default)]
+pub struct GamepadSticks {
+    /// Moves the player
+    pub left: Vec2,
+    /// Moves the virtual cursor
+    pub right: Vec2,
+}
+
+/// The bindings of every action, an action is pressed while any of its
+/// bindings is. `config/input.ron` only needs the actions it rebinds.
+///
+/// ```ron
+/// (
+///     bindings: {
+///         MoveUp: [Key(KeyW), Gamepad(DPadUp)],
+///         SpawnCube: [Mouse(Left), Gamepad(RightTrigger2)],
+///     },
+/// )
+/// ```
+#[derive(Resource, Serialize, Deserialize)]
+pub struct InputMap {
+    pub bindings: HashMap<Action, Vec<Binding>>,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        use Binding::{Gamepad as Pad, Key, Mouse};
+        // the editing actions are keyboard only, the gamepad runs out of buttons
+        let bindings = [
+            (
+                Action::MoveUp,
+                vec![Key(KeyCode::KeyW), Pad(GamepadButton::DPadUp)],
+            ),
+            (
+                Action::MoveDown,
+                vec![Key(KeyCode::KeyS), Pad(GamepadButton::DPadDown)],
+            ),
+            (
+                Action::MoveLeft,
+                vec![Key(KeyCode::KeyA), Pad(GamepadButton::DPadLeft)],
+            ),
+            (
+                Action::MoveRight,
+                vec![Key(KeyCode::KeyD), Pad(GamepadButton::DPadRight)],
+            ),
+            (
+                Action::ChangeShape,
+                vec![Key(KeyCode::KeyG), Pad(GamepadButton::RightTrigger)],
+            ),
+            (
+                Action::PreviousShape,
+                vec![Key(KeyCode::KeyF), Pad(GamepadButton::LeftTrigger)],
+            ),
+            (
+                Action::SpawnCube,
+                vec![Mouse(MouseButton::Left), Pad(GamepadButton::RightTrigger2)],
+            ),
+            (
+                Action::DeleteCube,
+                vec![Mouse(MouseButton::Right), Pad(GamepadButton::LeftTrigger2)],
+            ),
+            (
+                Action::ToggleClickMode,
+                vec![Key(KeyCode::Tab), Pad(GamepadButton::Select)],
+            ),
+            (
+                Action::Jump,
+                vec![Key(KeyCode::Space), Pad(GamepadButton::South)],
+            ),
+            (
+                Action::ToggleMovementMode,
+                vec![Key(KeyCode::KeyV), Pad(GamepadButton::North)],
+            ),
+            (
+                Action::Dig,
+                vec![Key(KeyCode::KeyE), Pad(GamepadButton::West)],
+            ),
+            (
+                Action::CancelSelection,
+                vec![Key(KeyCode::Escape), Pad(GamepadButton::East)],
+            ),
+            (
+                Action::DeleteJoint,
+                vec![
+                    Key(KeyCode::Delete),
+                    Key(KeyCode::Backspace),
+                    Pad(GamepadButton::East),
+                ],
+            ),
+            (Action::JointLonger, vec![Key(KeyCode::ArrowUp)]),
+            (Action::JointShorter, vec![Key(KeyCode::ArrowDown)]),
+            (Action::JointStronger, vec![Key(KeyCode::ArrowRight)]),
+            (Action::JointWeaker, vec![Key(KeyCode::ArrowLeft)]),
+            (Action::ToggleJointBreakable, vec![Key(KeyCode::KeyB)]),
+            (Action::JointForceUp, vec![Key(KeyCode::PageUp)]),
+            (Action::JointForceDown, vec![Key(KeyCode::PageDown)]),
+            (Action::SaveContraption, vec![Key(KeyCode::F5)]),
+            (Action::LoadContraption, vec![Key(KeyCode::F9)]),
+            (Action::ExportTiles, vec![Key(KeyCode::F6)]),
+            (Action::ToggleChainPin, vec![Key(KeyCode::KeyP)]),
+        ];
+        Self {
+            bindings: bindings.into_iter().collect(),
+        }
+    }
+}
+
+/// Run condition like `input_just_pressed`, for an [`Action`]
+pub fn action_just_pressed(action: Action) -> impl Fn(Res<ButtonInput<Action>>) -> bool + Clone {
+    move |actions: Res<ButtonInput<Action>>| actions.just_pressed(action)
+}
+
+/// The file bindings replace the default ones action by action, actions
+/// missing from an older file keep their default bindings
+fn load_input_map(mut commands: Commands) {
+    match read_ron::<InputMap>(INPUT_MAP_PATH) {
+        Ok(file) => {
+            let mut input_map = InputMap::default();
+            input_map.bindings.extend(file.bindings);
+            commands.insert_resource(input_map);
+        }
+        Err(err) => warn!("{err}, using the default bindings"),
+    }
+}
+
+fn update_actions(
+    input_map: Res<InputMap>,
+    mut actions: ResMut<ButtonInput<Action>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+    mut sticks: ResMut<GamepadSticks>,
+) {
+    sticks.left = gamepads
+        .iter()
+        .map(Gamepad::left_stick)
+        .sum::<Vec2>()
+        .clamp_length_max(1.0);
+    sticks.right = gamepads
+        .iter()
+        .map(Gamepad::right_stick)
+        .sum::<Vec2>()
+        .clamp_length_max(1.0);
+
+    actions.clear();
+    for (action, bindings) in &input_map.bindings {
+        let pressed = bindings.iter().any(|binding| match *binding {
+            Binding::Key(key) => keys.pressed(key),
+            Binding::Mouse(button) => mouse.pressed(button),
+            Binding::Gamepad(button) => gamepads.iter().any(|gamepad| gamepad.pressed(button)),
+        });
+        if pressed {
+            actions.press(*action);
+        } else {
+            actions.release(*action);
+        }
+    }
+}
@@ -0,0 +1,414 @@
+use bevy::{ecs::query::QueryFilter, prelude::*};
+use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Registers the [`JointSelection`] used by the JoinCube click mode and keeps
+/// joint entities in sync with their [`JointSpec`]
+pub struct JointPlugin;
+impl Plugin for JointPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<JointSelection>()
+            .add_event::<JointBroken>()
+            .add_systems(
+                Update,
+                (
+                    sync_joint_spec,
+                    break_overloaded_joints,
+                    despawn_dangling_joints,
+                ),
+            );
+    }
+}
+
+/// A joint entity, spawned as child of `body2` so that a body can have
+/// any number of joints (see `ImpulseJoint` docs)
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Joint {
+    /// the `ImpulseJoint::parent`
+    pub body1: Entity,
+    pub body2: Entity,
+}
+
+impl Joint {
+    /// World position of both anchors, from the `GlobalTransform` or the
+    /// `Transform` of the bodies, which are the same for bodies without parent
+    pub fn world_anchors<T, F>(
+        &self,
+        spec: &JointSpec,
+        transforms: &Query<&T, F>,
+    ) -> Option<(Vec2, Vec2)>
+    where
+        T: Component + Copy + Into<GlobalTransform>,
+        F: QueryFilter,
+    {
+        let transform1: GlobalTransform = (*transforms.get(self.body1).ok()?).into();
+        let transform2: GlobalTransform = (*transforms.get(self.body2).ok()?).into();
+        Some((
+            transform1
+                .transform_point(spec.anchor1.extend(0.0))
+                .truncate(),
+            transform2
+                .transform_point(spec.anchor2.extend(0.0))
+                .truncate(),
+        ))
+    }
+}
+
+/// Force applied by the joint during the last physics step, in rapier units.
+/// Locked axes, limits (ropes) and motors (springs) each hold their own part
+/// of the impulse on the linear axes
+pub fn joint_force(context: &RapierContext, handle: &RapierImpulseJointHandle) -> Option<f32> {
+    let joint = context.joints.impulse_joints.get(handle.0)?;
+    let dt = context.simulation.integration_parameters.dt;
+    let axis_impulse =
+        |i: usize| joint.impulses[i] + joint.data.limits[i].impulse + joint.data.motors[i].impulse;
+    let impulse = Vec2::new(axis_impulse(0), axis_impulse(1)).length();
+    (dt > 0.0).then(|| impulse / dt)
+}
+
+pub fn spawn_joint(
+    commands: &mut Commands,
+    body1: Entity,
+    body2: Entity,
+    spec: JointSpec,
+) -> Entity {
+    commands
+        .spawn((
+            Joint { body1, body2 },
+            spec,
+            spec.impulse_joint(body1),
+            ChildOf(body2),
+        ))
+        .id()
+}
+
+/// Rebuild the rapier joint after its spec has been edited
+fn sync_joint_spec(mut joints: Query<(&JointSpec, &mut ImpulseJoint), Changed<JointSpec>>) {
+    for (spec, mut joint) in &mut joints {
+        joint.data = spec.build();
+    }
+}
+
+/// Sent when a joint is removed because its force went over `JointSpec::max_force`
+#[derive(Event, Clone, Copy, Debug)]
+pub struct JointBroken {
+    pub body1: Entity,
+    pub body2: Entity,
+    pub force: f32,
+}
+
+fn break_overloaded_joints(
+    mut commands: Commands,
+    joints: Query<(Entity, &Joint, &JointSpec, &RapierImpulseJointHandle)>,
+    rapier_context: ReadRapierContext,
+    mut joint_broken: EventWriter<JointBroken>,
+) {
+    let Ok(context) = rapier_context.single() else {
+        return;
+    };
+    for (entity, joint, spec, handle) in &joints {
+        let Some(max_force) = spec.max_force else {
+            continue;
+        };
+        let Some(force) = joint_force(&context, handle) else {
+            continue;
+        };
+        if force > max_force {
+            commands.entity(entity).despawn();
+            joint_broken.write(JointBroken {
+                body1: joint.body1,
+                body2: joint.body2,
+                force,
+            });
+        }
+    }
+}
+
+/// Joints are despawned with body2 (their parent) but not with body1, and
+/// lose their parent if body2 was gone when they were spawned
+fn despawn_dangling_joints(
+    mut commands: Commands,
+    joints: Query<(Entity, &Joint)>,
+    bodies: Query<(), With<RigidBody>>,
+) {
+    for (entity, joint) in &joints {
+        if !bodies.contains(joint.body1) || !bodies.contains(joint.body2) {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Every joint type supported by bevy_rapier2d with its own parameters
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum JointKind {
+    /// Limits the max distance between the anchors
+    Rope { length: f32 },
+    /// Locks relative position and rotation
+    Fixed,
+    /// Free rotation around the anchors, `limits` are angles in radians
+    Revolute {
+        limits: Option<[f32; 2]>,
+        motor: Option<JointMotorSpec>,
+    },
+    /// Slides along `axis` (body1 local coord), `limits` are distances along it
+    Prismatic {
+        axis: Vec2,
+        limits: Option<[f32; 2]>,
+        motor: Option<JointMotorSpec>,
+    },
+    /// Pulls the anchors toward `rest_length`
+    Spring {
+        rest_length: f32,
+        stiffness: f32,
+        damping: f32,
+    },
+}
+
+/// A velocity motor driving a revolute or prismatic joint
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct JointMotorSpec {
+    pub target_velocity: f32,
+    pub factor: f32,
+}
+
+impl JointKind {
+    /// Scale the length-like parameter: rope length, spring rest length,
+    /// revolute and prismatic limits
+    pub fn scale_size(&mut self, factor: f32) {
+        match self {
+            JointKind::Rope { length } => *length *= factor,
+            JointKind::Spring { rest_length, .. } => *rest_length *= factor,
+            JointKind::Revolute {
+                limits: Some(limits),
+                ..
+            }
+            | JointKind::Prismatic {
+                limits: Some(limits),
+                ..
+            } => {
+                limits[0] *= factor;
+                limits[1] *= factor;
+            }
+            _ => {}
+        }
+    }
+
+    /// Change the force-like parameter: spring stiffness, motor velocity
+    /// (a motor is added if missing)
+    pub fn add_strength(&mut self, delta: f32) {
+        match self {
+            JointKind::Spring { stiffness, .. } => *stiffness = (*stiffness + delta).max(0.0),
+            JointKind::Revolute { motor, .. } | JointKind::Prismatic { motor, .. } => {
+                let motor = motor.get_or_insert(JointMotorSpec {
+                    target_velocity: 0.0,
+                    factor: 1.0,
+                });
+                motor.target_velocity += delta;
+            }
+            _ => {}
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            JointKind::Rope { .. } => "Rope",
+            JointKind::Fixed => "Fixed",
+            JointKind::Revolute { .. } => "Revolute",
+            JointKind::Prismatic { .. } => "Prismatic",
+            JointKind::Spring { .. } => "Spring",
+        }
+    }
+}
+
+/// Everything needed to rebuild a rapier joint, kept next to the `ImpulseJoint`
+/// since the rapier data cannot be read back as a [`JointKind`]
+#[derive(Component, Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct JointSpec {
+    pub kind: JointKind,
+    /// body1 (the `ImpulseJoint::parent`) local coord
+    #[serde(default)]
+    pub anchor1: Vec2,
+    /// body2 local coord
+    #[serde(default)]
+    pub anchor2: Vec2,
+    /// The joint breaks when its force goes over this value (rapier units),
+    /// `None` makes it indestructible
+    #[serde(default)]
+    pub max_force: Option<f32>,
+}
+
+impl JointSpec {
+    pub fn new(kind: JointKind, anchor1: Vec2, anchor2: Vec2) -> Self {
+        Self {
+            kind,
+            anchor1,
+            anchor2,
+            max_force: None,
+        }
+    }
+
+    pub fn with_max_force(mut self, max_force: Option<f32>) -> Self {
+        self.max_force = max_force;
+        self
+    }
+
+    pub fn build(&self) -> TypedJoint {
+        let (anchor1, anchor2) = (self.anchor1, self.anchor2);
+        match self.kind {
+            JointKind::Rope { length } => RopeJointBuilder::new(length)
+                .local_anchor1(anchor1)
+                .local_anchor2(anchor2)
+                .into(),
+            JointKind::Fixed => FixedJointBuilder::new()
+                .local_anchor1(anchor1)
+                .local_anchor2(anchor2)
+                .into(),
+            JointKind::Revolute { limits, motor } => {
+                let mut joint = RevoluteJointBuilder::new()
+                    .local_anchor1(anchor1)
+                    .local_anchor2(anchor2);
+                if let Some(limits) = limits {
+                    joint = joint.limits(limits);
+                }
+                if let Some(motor) = motor {
+                    joint = joint.motor_velocity(motor.target_velocity, motor.factor);
+                }
+                joint.into()
+            }
+            JointKind::Prismatic {
+                axis,
+                limits,
+                motor,
+            } => {
+                let mut joint = PrismaticJointBuilder::new(axis)
+                    .local_anchor1(anchor1)
+                    .local_anchor2(anchor2);
+                if let Some(limits) = limits {
+                    joint = joint.limits(limits);
+                }
+                if let Some(motor) = motor {
+                    joint = joint.motor_velocity(motor.target_velocity, motor.factor);
+                }
+                joint.into()
+            }
+            JointKind::Spring {
+                rest_length,
+                stiffness,
+                damping,
+            } => SpringJointBuilder::new(rest_length, stiffness, damping)
+                .local_anchor1(anchor1)
+                .local_anchor2(anchor2)
+                .into(),
+        }
+    }
+
+    /// The `ImpulseJoint` to insert on body2 or one of its children
+    pub fn impulse_joint(&self, body1: Entity) -> ImpulseJoint {
+        ImpulseJoint::new(body1, self.build())
+    }
+}
+
+/// The joint type used by JoinCube, one preset per type so that switching
+/// back and forth keeps the parameters
+#[derive(Resource)]
+pub struct JointSelection {
+    pub presets: Vec<JointKind>,
+    pub current: usize,
+    /// `JointSpec::max_force` of the new joints
+    pub max_force: Option<f32>,
+}
+
+impl Default for JointSelection {
+    fn default() -> Self {
+        Self {
+            presets: vec![
+                JointKind::Rope { length: 80.0 },
+                JointKind::Fixed,
+                JointKind::Revolute {
+                    limits: None,
+                    motor: None,
+                },
+                JointKind::Prismatic {
+                    axis: Vec2::X,
+                    limits: Some([-40.0, 40.0]),
+                    motor: None,
+                },
+                JointKind::Spring {
+                    rest_length: 80.0,
+                    stiffness: 20.0,
+                    damping: 0.5,
+                },
+            ],
+            current: 0,
+            max_force: None,
+        }
+    }
+}
+
+impl JointSelection {
+    pub fn kind(&self) -> JointKind {
+        self.presets[self.current]
+    }
+
+    pub fn spec(&self, anchor1: Vec2, anchor2: Vec2) -> JointSpec {
+        JointSpec::new(self.kind(), anchor1, anchor2).with_max_force(self.max_force)
+    }
+
+    pub fn next(&mut self) -> JointKind {
+        self.current = (self.current + 1) % self.presets.len();
+        self.kind()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    #[test]
+    fn rope_under_load_reports_its_force() {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            RapierPhysicsPlugin::<NoUserData>::default(),
+        ))
+        .insert_resource(TimestepMode::Fixed {
+            dt: 1.0 / 60.0,
+            substeps: 1,
+        });
+
+        let anchor = app
+            .world_mut()
+            .spawn((RigidBody::Fixed, Transform::default()))
+            .id();
+        // hanging below the anchor, pulled down by gravity past the rope length
+        let weight = app
+            .world_mut()
+            .spawn((
+                RigidBody::Dynamic,
+                Collider::ball(5.0),
+                Transform::from_xyz(0.0, -20.0, 0.0),
+            ))
+            .id();
+        let spec = JointSpec::new(JointKind::Rope { length: 20.0 }, Vec2::ZERO, Vec2::ZERO);
+        app.world_mut()
+            .spawn((spec.impulse_joint(anchor), ChildOf(weight)));
+
+        for _ in 0..30 {
+            app.update();
+        }
+
+        let force = app
+            .world_mut()
+            .run_system_once(
+                |rapier_context: ReadRapierContext, handles: Query<&RapierImpulseJointHandle>| {
+                    let context = rapier_context.single().ok()?;
+                    joint_force(&context, handles.single().ok()?)
+                },
+            )
+            .expect("the system runs");
+        assert!(force.is_some_and(|force| force > 0.0), "{force:?}");
+    }
+}
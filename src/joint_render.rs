@@ -0,0 +1,213 @@
+use bevy::{
+    color::palettes::css::{GRAY, LIGHT_SKY_BLUE, ORANGE, SANDY_BROWN, SPRING_GREEN},
+    prelude::*,
+    sprite::AlphaMode2d,
+};
+use bevy_rapier2d::prelude::*;
+
+use crate::joint::{joint_force, Joint, JointBroken, JointKind, JointSpec};
+
+/// Thickness of the line drawn for every joint
+const JOINT_LINE_WIDTH: f32 = 3.0;
+/// Drawn above the bodies
+const JOINT_LINE_Z: f32 = 5.0;
+/// Force at which a joint line without max force is fully red
+const JOINT_FULL_TENSION: f32 = 5_000.0;
+/// How long the flash of a broken joint lasts
+const JOINT_SNAP_SECS: f32 = 0.3;
+
+/// Draws every [`Joint`] as a mesh line between its world anchors,
+/// colored by joint type and tension
+pub struct JointRenderPlugin;
+impl Plugin for JointRenderPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<JointLineMesh>()
+            .add_systems(
+                PostUpdate,
+                update_joint_lines
+                    .after(PhysicsSet::Writeback)
+                    .before(TransformSystem::TransformPropagate),
+            )
+            .add_systems(Update, (spawn_joint_snaps, fade_joint_snaps))
+            .add_observer(on_add_joint_spawn_line)
+            .add_observer(on_remove_joint_despawn_line);
+    }
+}
+
+/// Unit square stretched along each joint
+#[derive(Resource)]
+struct JointLineMesh(Handle<Mesh>);
+
+/// The line entity drawing a joint
+#[derive(Component)]
+struct JointLine {
+    joint: Entity,
+}
+
+/// Stored on the joint entity to despawn its line with it
+#[derive(Component)]
+struct JointLineEntity(Entity);
+
+impl FromWorld for JointLineMesh {
+    fn from_world(world: &mut World) -> Self {
+        let mut meshes = world.resource_mut::<Assets<Mesh>>();
+        JointLineMesh(meshes.add(Rectangle::new(1.0, 1.0)))
+    }
+}
+
+pub fn joint_color(kind: &JointKind) -> Color {
+    match kind {
+        JointKind::Rope { .. } => SANDY_BROWN.into(),
+        JointKind::Fixed => GRAY.into(),
+        JointKind::Revolute { .. } => LIGHT_SKY_BLUE.into(),
+        JointKind::Prismatic { .. } => ORANGE.into(),
+        JointKind::Spring { .. } => SPRING_GREEN.into(),
+    }
+}
+
+fn on_add_joint_spawn_line(
+    trigger: Trigger<OnAdd, Joint>,
+    mut commands: Commands,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    line_mesh: Res<JointLineMesh>,
+) {
+    let joint = trigger.target();
+    let line = commands
+        .spawn((
+            JointLine { joint },
+            Mesh2d(line_mesh.0.clone()),
+            MeshMaterial2d(materials.add(ColorMaterial::default())),
+            Transform::default(),
+            Visibility::Hidden,
+            // clicks go through to the bodies under the line
+            Pickable::IGNORE,
+        ))
+        .id();
+    commands.entity(joint).insert(JointLineEntity(line));
+}
+
+fn on_remove_joint_despawn_line(
+    trigger: Trigger<OnRemove, Joint>,
+    lines: Query<&JointLineEntity>,
+    mut commands: Commands,
+) {
+    if let Ok(line) = lines.get(trigger.target()) {
+        commands.entity(line.0).despawn();
+    }
+}
+
+fn update_joint_lines(
+    joints: Query<(&Joint, &JointSpec, Option<&RapierImpulseJointHandle>)>,
+    mut lines: Query<(
+        &JointLine,
+        &mut Transform,
+        &mut Visibility,
+        &MeshMaterial2d<ColorMaterial>,
+    )>,
+    // the bodies have no parent, their Transform is already the one rapier
+    // wrote back this frame while their GlobalTransform is not propagated yet
+    transforms: Query<&Transform, Without<JointLine>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    rapier_context: ReadRapierContext,
+) {
+    let context = rapier_context.single().ok();
+    for (line, mut transform, mut visibility, material) in &mut lines {
+        let Some((joint, spec, handle)) = joints.get(line.joint).ok() else {
+            continue;
+        };
+        let Some((anchor1, anchor2)) = joint.world_anchors(spec, &transforms) else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+
+        let segment = anchor2 - anchor1;
+        let middle = (anchor1 + anchor2) / 2.0;
+        *transform = Transform::from_xyz(middle.x, middle.y, JOINT_LINE_Z)
+            .with_rotation(Quat::from_rotation_z(segment.to_angle()))
+            .with_scale(Vec3::new(segment.length().max(1.0), JOINT_LINE_WIDTH, 1.0));
+        *visibility = Visibility::Visible;
+
+        let force = context
+            .as_ref()
+            .zip(handle)
+            .and_then(|(context, handle)| joint_force(context, handle))
+            .unwrap_or(0.0);
+        if let Some(material) = materials.get_mut(&material.0) {
+            material.color = tension_color(spec, force);
+        }
+    }
+}
+
+/// The joint type color turning red as `force` gets close to the max force
+fn tension_color(spec: &JointSpec, force: f32) -> Color {
+    let tension = (force / spec.max_force.unwrap_or(JOINT_FULL_TENSION)).clamp(0.0, 1.0);
+    LinearRgba::from(joint_color(&spec.kind))
+        .mix(&LinearRgba::RED, tension)
+        .into()
+}
+
+/// Flash left where a joint broke
+#[derive(Component)]
+struct JointSnap(Timer);
+
+fn spawn_joint_snaps(
+    mut commands: Commands,
+    mut joint_broken: EventReader<JointBroken>,
+    transforms: Query<&GlobalTransform>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for broken in joint_broken.read() {
+        let Ok([transform1, transform2]) = transforms.get_many([broken.body1, broken.body2]) else {
+            continue;
+        };
+        let middle = (transform1.translation() + transform2.translation()) / 2.0;
+        // stronger joints leave a bigger flash
+        let radius = 8.0 + broken.force.log10().max(0.0) * 4.0;
+        commands.spawn((
+            JointSnap(Timer::from_seconds(JOINT_SNAP_SECS, TimerMode::Once)),
+            Mesh2d(meshes.add(Circle::new(radius))),
+            MeshMaterial2d(materials.add(ColorMaterial {
+                color: Color::WHITE,
+                alpha_mode: AlphaMode2d::Blend,
+                ..default()
+            })),
+            Transform::from_xyz(middle.x, middle.y, JOINT_LINE_Z),
+            Pickable::IGNORE,
+        ));
+    }
+}
+
+fn fade_joint_snaps(
+    mut commands: Commands,
+    mut snaps: Query<(Entity, &mut JointSnap, &MeshMaterial2d<ColorMaterial>)>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    time: Res<Time>,
+) {
+    for (entity, mut snap, material) in &mut snaps {
+        snap.0.tick(time.delta());
+        if snap.0.finished() {
+            commands.entity(entity).despawn();
+        } else if let Some(material) = materials.get_mut(&material.0) {
+            material.color.set_alpha(snap.0.fraction_remaining());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loaded_rope_turns_red() {
+        let spec = JointSpec::new(JointKind::Rope { length: 20.0 }, Vec2::ZERO, Vec2::ZERO)
+            .with_max_force(Some(100.0));
+        let rope = LinearRgba::from(joint_color(&spec.kind));
+
+        assert_eq!(LinearRgba::from(tension_color(&spec, 0.0)), rope);
+        let half = LinearRgba::from(tension_color(&spec, 50.0));
+        assert!(half.red > rope.red && half != LinearRgba::RED);
+        let full = LinearRgba::from(tension_color(&spec, 500.0));
+        assert!((full.red - 1.0).abs() < 1e-5 && full.green.abs() < 1e-5);
+    }
+}
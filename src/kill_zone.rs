@@ -0,0 +1,134 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    player::{lifecycle_plugin::PlayerDied, player_plugin::Player, resources::PlayerSpawnPoint},
+    robot_constructor::RobotPart,
+};
+
+/// Despawns the rigid bodies entering a [`KillZone`], the player robot dies
+/// instead and respawns at its last [`Checkpoint`]
+pub struct KillZonePlugin;
+impl Plugin for KillZonePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<KillZones>()
+            .add_systems(Update, reach_checkpoint)
+            .add_systems(PostUpdate, apply_kill_zones);
+    }
+}
+
+/// A region of the world where bodies die
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum KillZone {
+    /// Everything under `height`
+    Below { height: f32 },
+    /// An axis aligned box
+    Box { min: Vec2, max: Vec2 },
+}
+
+impl KillZone {
+    pub fn contains(&self, point: Vec2) -> bool {
+        match *self {
+            KillZone::Below { height } => point.y < height,
+            KillZone::Box { min, max } => point.cmpge(min).all() && point.cmple(max).all(),
+        }
+    }
+}
+
+/// The kill zones of the current level
+#[derive(Resource)]
+pub struct KillZones(pub Vec<KillZone>);
+
+impl Default for KillZones {
+    fn default() -> Self {
+        Self(vec![KillZone::Below { height: -7400.0 }])
+    }
+}
+
+impl KillZones {
+    pub fn contains(&self, point: Vec2) -> bool {
+        self.0.iter().any(|zone| zone.contains(point))
+    }
+}
+
+/// Entering it moves the [`PlayerSpawnPoint`] to its center
+#[derive(Component)]
+pub struct Checkpoint;
+
+/// The sensor accepted by `is_sensor` that a part of the player robot
+/// started touching in `collision`
+pub fn player_sensor_hit(
+    collision: &CollisionEvent,
+    is_sensor: impl Fn(Entity) -> bool,
+    player: &Query<(), With<Player>>,
+    parts: &Query<&RobotPart>,
+) -> Option<Entity> {
+    let CollisionEvent::Started(entity1, entity2, _) = *collision else {
+        return None;
+    };
+    let is_player = |entity: Entity| {
+        parts
+            .get(entity)
+            .is_ok_and(|part| player.contains(part.robot))
+    };
+    match (entity1, entity2) {
+        (sensor, other) | (other, sensor) if is_sensor(sensor) && is_player(other) => Some(sensor),
+        _ => None,
+    }
+}
+
+fn reach_checkpoint(
+    mut collisions: EventReader<CollisionEvent>,
+    checkpoints: Query<&GlobalTransform, With<Checkpoint>>,
+    player: Query<(), With<Player>>,
+    parts: Query<&RobotPart>,
+    mut spawn_point: ResMut<PlayerSpawnPoint>,
+) {
+    for collision in collisions.read() {
+        let Some(checkpoint) = player_sensor_hit(
+            collision,
+            |entity| checkpoints.contains(entity),
+            &player,
+            &parts,
+        ) else {
+            continue;
+        };
+        let Ok(transform) = checkpoints.get(checkpoint) else {
+            continue;
+        };
+        let position = transform.translation().truncate();
+        if spawn_point.0 != position {
+            info!("checkpoint reached");
+            spawn_point.0 = position;
+        }
+    }
+}
+
+/// Only dynamic and kinematic bodies are affected, a player robot with any
+/// part inside a zone dies and respawns at the last checkpoint
+fn apply_kill_zones(
+    mut commands: Commands,
+    kill_zones: Res<KillZones>,
+    bodies: Query<(Entity, &RigidBody, &Transform, Option<&RobotPart>)>,
+    player: Query<(), With<Player>>,
+) {
+    let mut dead_robots = Vec::new();
+    for (entity, body, transform, part) in &bodies {
+        let position = transform.translation.truncate();
+        // the terrain and the painted tiles never die
+        if *body == RigidBody::Fixed || !kill_zones.contains(position) {
+            continue;
+        }
+        match part {
+            Some(part) if player.contains(part.robot) => dead_robots.push((part.robot, position)),
+            _ => commands.entity(entity).despawn(),
+        }
+    }
+    dead_robots.sort_by_key(|(robot, _)| *robot);
+    dead_robots.dedup_by_key(|(robot, _)| *robot);
+
+    for (player, position) in dead_robots {
+        commands.trigger(PlayerDied { player, position });
+    }
+}
@@ -0,0 +1,269 @@
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext},
+    prelude::*,
+};
+use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    config::{read_ron_asset, RonFileError},
+    cube::Cube,
+    cube_chain::{ChainPin, CubeChainBuilder},
+    kill_zone::{player_sensor_hit, Checkpoint, KillZone, KillZones},
+    player::{player_plugin::Player, resources::PlayerSpawnPoint},
+    robot_constructor::{despawn_robot, RobotPart},
+    save_load::SavedContraption,
+    terrain::{
+        terrain_plugin::TerrainSettings,
+        tile_editor_plugin::{spawn_tile, PaintedLevel, PaintedTiles, TerrainTile, TileEditor},
+    },
+};
+
+/// The level loaded at startup, edit it while the game runs to rebuild the world
+const LEVEL_PATH: &str = "levels/default.level.ron";
+
+/// Loads the [`Level`] asset from `assets/levels/*.level.ron` and tears down
+/// and rebuilds the world every time it is loaded or modified
+pub struct LevelPlugin;
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Level>()
+            .init_asset_loader::<LevelLoader>()
+            .add_event::<GoalReached>()
+            .add_systems(Startup, load_level)
+            .add_systems(Update, (build_level, reach_goal));
+    }
+}
+
+/// Everything placed in the world by a level file, missing fields take their
+/// default value.
+///
+/// ```ron
+/// (
+///     terrain: (generator: Hills(height: -100.0, amplitude: 150.0, wavelength: 400.0, octaves: 3), seed: 7),
+///     player_spawn: (0.0, 100.0),
+///     contraption: (
+///         cubes: [
+///             (shape: Rectangle(width: 15.0, height: 15.0), color: LinearRgba((red: 1.0, green: 0.5, blue: 0.0, alpha: 1.0)), transform: (translation: (200.0, 0.0, 0.0), rotation: (0.0, 0.0, 0.0, 1.0), scale: (1.0, 1.0, 1.0))),
+///         ],
+///     ),
+///     checkpoints: [(center: (1000.0, 0.0), half_size: (20.0, 100.0))],
+///     chains: [(start: (400.0, 0.0), end: (700.0, 0.0), pin_end: true)],
+///     goals: [(center: (2000.0, 0.0), half_size: (50.0, 100.0))],
+///     kill_zones: [Below(height: -3000.0), Box(min: (500.0, -500.0), max: (700.0, -200.0))],
+/// )
+/// ```
+#[derive(Asset, TypePath, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Level {
+    pub terrain: TerrainSettings,
+    pub player_spawn: Vec2,
+    /// Pre-placed cubes and the joints between them
+    pub contraption: SavedContraption,
+    /// Chains of cubes such as bridges and ropes
+    pub chains: Vec<CubeChainBuilder>,
+    pub tiles: PaintedLevel,
+    /// Reaching one moves the player spawn point to its center
+    pub checkpoints: Vec<ZoneBlueprint>,
+    pub goals: Vec<ZoneBlueprint>,
+    pub kill_zones: Vec<KillZone>,
+}
+
+impl Default for Level {
+    fn default() -> Self {
+        Self {
+            terrain: TerrainSettings::default(),
+            player_spawn: Vec2::ZERO,
+            contraption: SavedContraption::default(),
+            chains: Vec::new(),
+            tiles: PaintedLevel::default(),
+            checkpoints: Vec::new(),
+            goals: Vec::new(),
+            kill_zones: KillZones::default().0,
+        }
+    }
+}
+
+impl Level {
+    /// Check that every joint and pin refers to an existing cube
+    pub fn validate(&self) -> Result<(), LevelError> {
+        let cubes = self.contraption.cubes.len();
+        let bodies = self
+            .contraption
+            .joints
+            .iter()
+            .flat_map(|joint| [joint.body1, joint.body2])
+            .chain(self.contraption.pins.iter().map(|pin| pin.body));
+        for body in bodies {
+            if body >= cubes {
+                return Err(LevelError::UnknownCube(body));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A rectangle of the level, `half_size` follows rapier cuboids
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct ZoneBlueprint {
+    pub center: Vec2,
+    pub half_size: Vec2,
+}
+
+impl ZoneBlueprint {
+    /// A sensor reporting the bodies entering the zone
+    fn sensor(&self) -> impl Bundle {
+        (
+            Collider::cuboid(self.half_size.x, self.half_size.y),
+            Sensor,
+            ActiveEvents::COLLISION_EVENTS,
+            Transform::from_translation(self.center.extend(0.0)),
+        )
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum LevelError {
+    #[error("could not load level: {0}")]
+    File(#[from] RonFileError),
+    #[error("level joint or pin refers to missing cube {0}")]
+    UnknownCube(usize),
+}
+
+#[derive(Default)]
+pub struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    type Asset = Level;
+    type Settings = ();
+    type Error = LevelError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let level: Level = read_ron_asset(reader, load_context).await?;
+        level.validate()?;
+        Ok(level)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
+
+/// Marks every entity spawned by the current level
+#[derive(Component)]
+pub struct LevelEntity;
+
+#[derive(Component)]
+pub struct GoalZone;
+
+/// Sent when a part of the player robot enters a [`GoalZone`]
+#[derive(Event, Clone, Copy, Debug)]
+pub struct GoalReached;
+
+/// Handle of the level the world is built from
+#[derive(Resource)]
+pub struct CurrentLevel(pub Handle<Level>);
+
+fn load_level(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(CurrentLevel(asset_server.load(LEVEL_PATH)));
+}
+
+/// Replace the cubes, chains, tiles, checkpoints, goals and player with the ones of the level
+/// whenever it is loaded or its file changes, the terrain is rebuilt by
+/// stream_terrain_chunks when its settings change
+#[allow(clippy::too_many_arguments)]
+fn build_level(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<Level>>,
+    current_level: Res<CurrentLevel>,
+    levels: Res<Assets<Level>>,
+    old_entities: Query<
+        Entity,
+        Or<(
+            With<LevelEntity>,
+            With<Cube>,
+            With<TerrainTile>,
+            With<ChainPin>,
+        )>,
+    >,
+    player: Query<Entity, With<Player>>,
+    parts: Query<(Entity, &RobotPart)>,
+    mut terrain: ResMut<TerrainSettings>,
+    mut tile_editor: ResMut<TileEditor>,
+    mut painted: ResMut<PaintedTiles>,
+    mut kill_zones: ResMut<KillZones>,
+    mut spawn_point: ResMut<PlayerSpawnPoint>,
+) {
+    if !events.read().any(|event| {
+        event.is_loaded_with_dependencies(&current_level.0) || event.is_modified(&current_level.0)
+    }) {
+        return;
+    }
+    let Some(level) = levels.get(&current_level.0) else {
+        return;
+    };
+
+    old_entities
+        .iter()
+        .for_each(|entity| commands.entity(entity).despawn());
+    // spawn_player spawns it again at the new spawn point
+    for player in &player {
+        despawn_robot(&mut commands, player, &parts);
+    }
+
+    *terrain = level.terrain.clone();
+    kill_zones.0 = level.kill_zones.clone();
+    spawn_point.0 = level.player_spawn;
+
+    for cube in level.contraption.spawn(&mut commands) {
+        commands.entity(cube).insert(LevelEntity);
+    }
+
+    for chain in &level.chains {
+        for segment in chain.spawn(&mut commands) {
+            commands.entity(segment).insert(LevelEntity);
+        }
+    }
+
+    tile_editor.tile_size = level.tiles.tile_size;
+    painted.0.clear();
+    for tile in &level.tiles.tiles {
+        let entity = spawn_tile(
+            &mut commands,
+            level.tiles.tile_size,
+            tile.cell,
+            tile.material,
+        );
+        commands.entity(entity).insert(LevelEntity);
+        painted.0.insert(tile.cell, entity);
+    }
+
+    for checkpoint in &level.checkpoints {
+        commands.spawn((LevelEntity, Checkpoint, checkpoint.sensor()));
+    }
+    for goal in &level.goals {
+        commands.spawn((LevelEntity, GoalZone, goal.sensor()));
+    }
+}
+
+fn reach_goal(
+    mut collisions: EventReader<CollisionEvent>,
+    goals: Query<(), With<GoalZone>>,
+    player: Query<(), With<Player>>,
+    parts: Query<&RobotPart>,
+    mut goal_reached: EventWriter<GoalReached>,
+) {
+    for collision in collisions.read() {
+        if player_sensor_hit(collision, |entity| goals.contains(entity), &player, &parts).is_some()
+        {
+            info!("goal reached");
+            goal_reached.write(GoalReached);
+        }
+    }
+}
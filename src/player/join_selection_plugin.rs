@@ -0,0 +1,106 @@
+use bevy::{color::palettes::css::GOLD, prelude::*};
+
+use crate::{input::Action, joint::JointSelection, joint_render::joint_color};
+
+use super::{
+    player_plugin::{ClickMode, PairEntitySelection, Player},
+    resources::PlayerMouseCoor,
+};
+
+/// How much bigger than the body its selection outline is
+const OUTLINE_SCALE: f32 = 1.2;
+
+/// Feedback for the JoinCube selection: outlines on the selected bodies,
+/// a preview line to the cursor, CancelSelection or DeleteCube to cancel
+pub struct JoinSelectionPlugin;
+
+impl Plugin for JoinSelectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                cancel_join_selection,
+                update_selection_outlines,
+                preview_join_line,
+            )
+                .chain(),
+        );
+    }
+}
+
+/// Drawn behind a selected body
+#[derive(Component)]
+struct SelectionOutline;
+
+fn cancel_join_selection(
+    player: Single<(&Player, &mut PairEntitySelection)>,
+    actions: Res<ButtonInput<Action>>,
+) {
+    let (player, mut selection) = player.into_inner();
+    let is_empty = matches!(selection.0, (None, None));
+    // DeleteCube does not delete anything in JoinCube
+    let cancel = actions.any_just_pressed([Action::CancelSelection, Action::DeleteCube])
+        || player.0 != ClickMode::JoinCube;
+    if cancel && !is_empty {
+        *selection = PairEntitySelection::default();
+    }
+}
+
+fn update_selection_outlines(
+    mut commands: Commands,
+    selection: Single<&PairEntitySelection, Changed<PairEntitySelection>>,
+    outlines: Query<(Entity, &ChildOf), With<SelectionOutline>>,
+    meshes: Query<&Mesh2d>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let (ent1, ent2) = selection.0;
+    let selected: Vec<Entity> = [ent1, ent2]
+        .into_iter()
+        .flatten()
+        .map(|selected| selected.entity)
+        .collect();
+
+    for (outline, child_of) in &outlines {
+        if !selected.contains(&child_of.parent()) {
+            commands.entity(outline).despawn();
+        }
+    }
+    for entity in selected {
+        let has_outline = outlines
+            .iter()
+            .any(|(_, child_of)| child_of.parent() == entity);
+        let Ok(mesh) = meshes.get(entity) else {
+            continue;
+        };
+        if !has_outline {
+            commands.spawn((
+                SelectionOutline,
+                mesh.clone(),
+                MeshMaterial2d(materials.add(ColorMaterial::from_color(GOLD))),
+                Transform::from_xyz(0.0, 0.0, -0.1).with_scale(Vec3::splat(OUTLINE_SCALE)),
+                Pickable::IGNORE,
+                ChildOf(entity),
+            ));
+        }
+    }
+}
+
+fn preview_join_line(
+    mut gizmos: Gizmos,
+    selection: Single<&PairEntitySelection>,
+    transforms: Query<&GlobalTransform>,
+    mouse_coor: Res<PlayerMouseCoor>,
+    joint_selection: Res<JointSelection>,
+) {
+    let (Some(first), None) = selection.0 else {
+        return;
+    };
+    let Ok(transform) = transforms.get(first.entity) else {
+        return;
+    };
+    let anchor = transform
+        .transform_point(first.anchor.extend(0.0))
+        .truncate();
+    let cursor: Vec2 = mouse_coor.into_inner().into();
+    gizmos.line_2d(anchor, cursor, joint_color(&joint_selection.kind()));
+}
@@ -0,0 +1,147 @@
+use bevy::{color::palettes::css::YELLOW, prelude::*};
+
+use crate::{
+    input::Action,
+    joint::{Joint, JointSpec},
+};
+
+use super::{
+    player_plugin::{ClickMode, Player},
+    resources::PlayerMouseCoor,
+};
+
+/// Max distance between the cursor and a joint for it to be selected
+const JOINT_PICK_DISTANCE: f32 = 12.0;
+/// Break force given to an indestructible joint by ToggleJointBreakable
+const DEFAULT_JOINT_MAX_FORCE: f32 = 2_000.0;
+
+/// ClickMode::EditJoint: SpawnCube near a joint selects it, at the mouse or
+/// the virtual cursor, arrows change its parameters and Delete removes it
+pub struct JointEditPlugin;
+
+impl Plugin for JointEditPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SelectedJoint>().add_systems(
+            Update,
+            (select_joint, edit_selected_joint, highlight_selected_joint).chain(),
+        );
+    }
+}
+
+/// The joint entity being edited
+#[derive(Resource, Default)]
+pub struct SelectedJoint(pub Option<Entity>);
+
+fn distance_to_segment(point: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let ab = b - a;
+    let t = if ab.length_squared() > 0.0 {
+        ((point - a).dot(ab) / ab.length_squared()).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    point.distance(a + ab * t)
+}
+
+fn select_joint(
+    player: Single<&Player>,
+    actions: Res<ButtonInput<Action>>,
+    mouse_coor: Res<PlayerMouseCoor>,
+    joints: Query<(Entity, &Joint, &JointSpec)>,
+    transforms: Query<&GlobalTransform>,
+    mut selected: ResMut<SelectedJoint>,
+) {
+    if player.0 != ClickMode::EditJoint {
+        selected.0 = None;
+        return;
+    }
+    if !actions.just_pressed(Action::SpawnCube) {
+        return;
+    }
+    let cursor: Vec2 = mouse_coor.into_inner().into();
+    selected.0 = joints
+        .iter()
+        .filter_map(|(entity, joint, spec)| {
+            let (anchor1, anchor2) = joint.world_anchors(spec, &transforms)?;
+            Some((entity, distance_to_segment(cursor, anchor1, anchor2)))
+        })
+        .filter(|(_, distance)| *distance < JOINT_PICK_DISTANCE)
+        .min_by(|(_, d1), (_, d2)| d1.total_cmp(d2))
+        .map(|(entity, _)| entity);
+}
+
+/// Up/Down scale the joint length, Left/Right change its stiffness or motor,
+/// B toggles breakable, PageUp/PageDown scale the break force,
+/// Delete/Backspace remove it (default bindings)
+fn edit_selected_joint(
+    mut commands: Commands,
+    actions: Res<ButtonInput<Action>>,
+    mut selected: ResMut<SelectedJoint>,
+    mut specs: Query<&mut JointSpec>,
+) {
+    let Some(entity) = selected.0 else {
+        return;
+    };
+    let Ok(mut spec) = specs.get_mut(entity) else {
+        selected.0 = None;
+        return;
+    };
+
+    if actions.just_pressed(Action::DeleteJoint) {
+        commands.entity(entity).despawn();
+        selected.0 = None;
+        return;
+    }
+
+    let mut kind = spec.kind;
+    if actions.just_pressed(Action::JointLonger) {
+        kind.scale_size(1.1);
+    }
+    if actions.just_pressed(Action::JointShorter) {
+        kind.scale_size(1.0 / 1.1);
+    }
+    if actions.just_pressed(Action::JointStronger) {
+        kind.add_strength(1.0);
+    }
+    if actions.just_pressed(Action::JointWeaker) {
+        kind.add_strength(-1.0);
+    }
+    let mut max_force = spec.max_force;
+    if actions.just_pressed(Action::ToggleJointBreakable) {
+        max_force = match max_force {
+            Some(_) => None,
+            None => Some(DEFAULT_JOINT_MAX_FORCE),
+        };
+    }
+    if let Some(max_force) = max_force.as_mut() {
+        if actions.just_pressed(Action::JointForceUp) {
+            *max_force *= 1.5;
+        }
+        if actions.just_pressed(Action::JointForceDown) {
+            *max_force /= 1.5;
+        }
+    }
+    if kind != spec.kind || max_force != spec.max_force {
+        spec.kind = kind;
+        spec.max_force = max_force;
+        info!(
+            "joint {entity}: {:?}, max force {:?}",
+            spec.kind, spec.max_force
+        );
+    }
+}
+
+fn highlight_selected_joint(
+    mut gizmos: Gizmos,
+    selected: Res<SelectedJoint>,
+    joints: Query<(&Joint, &JointSpec)>,
+    transforms: Query<&GlobalTransform>,
+) {
+    let Some((joint, spec)) = selected.0.and_then(|entity| joints.get(entity).ok()) else {
+        return;
+    };
+    if let Some((anchor1, anchor2)) = joint.world_anchors(spec, &transforms) {
+        gizmos.line_2d(anchor1, anchor2, YELLOW);
+        gizmos.circle_2d(anchor1, 4.0, YELLOW);
+        gizmos.circle_2d(anchor2, 4.0, YELLOW);
+    }
+}
@@ -0,0 +1,72 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::robot_constructor::{despawn_robot, RobotPart};
+
+/// Despawns the player robot on [`PlayerDied`], spawn_player waits for
+/// [`PlayerRespawn`] before bringing it back at the spawn point
+pub struct PlayerLifecyclePlugin;
+impl Plugin for PlayerLifecyclePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlayerRespawn>()
+            .add_systems(Update, tick_respawn_timer)
+            .add_observer(on_player_died);
+    }
+}
+
+/// Triggered once the player robot is spawned, `player` is its root part
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PlayerSpawned {
+    pub player: Entity,
+}
+
+/// Trigger it to kill the player robot whose root part is `player`
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PlayerDied {
+    pub player: Entity,
+    pub position: Vec2,
+}
+
+/// Delay between the death of the player and its respawn
+#[derive(Resource)]
+pub struct PlayerRespawn {
+    pub delay: Duration,
+    timer: Option<Timer>,
+}
+
+impl Default for PlayerRespawn {
+    fn default() -> Self {
+        Self {
+            delay: Duration::from_secs_f32(1.5),
+            timer: None,
+        }
+    }
+}
+
+impl PlayerRespawn {
+    /// False while the player is dead and the delay is not over
+    pub fn ready(&self) -> bool {
+        self.timer.is_none()
+    }
+}
+
+fn tick_respawn_timer(mut respawn: ResMut<PlayerRespawn>, time: Res<Time>) {
+    let Some(timer) = respawn.timer.as_mut() else {
+        return;
+    };
+    if timer.tick(time.delta()).finished() {
+        respawn.timer = None;
+    }
+}
+
+fn on_player_died(
+    trigger: Trigger<PlayerDied>,
+    parts: Query<(Entity, &RobotPart)>,
+    mut respawn: ResMut<PlayerRespawn>,
+    mut commands: Commands,
+) {
+    info!("player died at {}", trigger.position);
+    despawn_robot(&mut commands, trigger.player, &parts);
+    respawn.timer = Some(Timer::new(respawn.delay, TimerMode::Once));
+}
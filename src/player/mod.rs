@@ -1,3 +1,8 @@
+pub mod join_selection_plugin;
+pub mod joint_edit_plugin;
+pub mod lifecycle_plugin;
 pub mod mouse_interaction_plugin;
+pub mod movement_plugin;
+pub mod player_forms_plugin;
 pub mod player_plugin;
 pub mod resources;
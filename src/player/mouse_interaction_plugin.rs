@@ -1,15 +1,57 @@
 use bevy::prelude::*;
 
-use crate::cube_chain::spawn_cube_chain;
+use crate::input::GamepadSticks;
 
-use super::resources::PlayerMouseCoor;
+use super::resources::{PlayerMouseCoor, VirtualCursor};
+
+/// Virtual cursor speed at full right stick, in viewport pixels per second
+const VIRTUAL_CURSOR_SPEED: f32 = 900.0;
 
 pub struct MouseInteractionPlugin;
 
 impl Plugin for MouseInteractionPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<PlayerMouseCoor>()
-            .add_systems(Startup, spawn_cube_chain);
+            .init_resource::<VirtualCursor>()
+            .add_systems(Update, (update_virtual_cursor, draw_virtual_cursor));
+    }
+}
+
+/// The right stick takes over the cursor starting from the mouse position,
+/// moving the mouse gives it back
+pub fn update_virtual_cursor(
+    mut virtual_cursor: ResMut<VirtualCursor>,
+    mut cursor_moved: EventReader<CursorMoved>,
+    sticks: Res<GamepadSticks>,
+    window: Single<&Window>,
+    time: Res<Time>,
+) {
+    if cursor_moved.read().count() > 0 {
+        virtual_cursor.active = false;
+    }
+    if sticks.right == Vec2::ZERO {
+        return;
+    }
+    if !virtual_cursor.active {
+        virtual_cursor.active = true;
+        virtual_cursor.position = window.cursor_position().unwrap_or(window.size() / 2.0);
+    }
+    // viewport y grows downward
+    let delta =
+        Vec2::new(sticks.right.x, -sticks.right.y) * VIRTUAL_CURSOR_SPEED * time.delta_secs();
+    virtual_cursor.position = (virtual_cursor.position + delta).clamp(Vec2::ZERO, window.size());
+}
+
+/// The system cursor does not follow the virtual one, draw it in its place
+fn draw_virtual_cursor(
+    virtual_cursor: Res<VirtualCursor>,
+    mouse_coor: Res<PlayerMouseCoor>,
+    mut gizmos: Gizmos,
+) {
+    if virtual_cursor.active {
+        let position: Vec2 = mouse_coor.into_inner().into();
+        gizmos.circle_2d(position, 8.0, Color::WHITE);
+        gizmos.circle_2d(position, 2.0, Color::WHITE);
     }
 }
 
@@ -0,0 +1,276 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    input::{Action, GamepadSticks},
+    terrain::terrain_plugin::Terrain,
+};
+
+use super::{player_forms_plugin::PlayerForms, player_plugin::Player};
+
+/// How far below the player the ground is looked for
+const GROUND_PROBE_DISTANCE: f32 = 4.0;
+/// Minimum upward component of the surface normal for it to count as
+/// ground, walls and ceilings are steeper
+const GROUND_MIN_NORMAL_Y: f32 = 0.7;
+
+/// Moves the player through its [`MovementController`], either flying in
+/// every direction or running and jumping on the terrain
+pub struct MovementPlugin;
+impl Plugin for MovementPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (toggle_movement_mode, buffer_jump))
+            .add_systems(FixedUpdate, (detect_ground, move_player).chain());
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MovementMode {
+    /// WASD moves in every direction, the velocity goes back to zero without input
+    Flight,
+    /// Only horizontal control, gravity pulls the robot down and it can jump
+    Platformer,
+}
+
+/// Tuning of the player movement, speeds are in pixels per second,
+/// rates in pixels per second squared and times in seconds, whatever the
+/// fixed timestep
+#[derive(Component, Clone, Copy)]
+pub struct MovementController {
+    pub mode: MovementMode,
+    pub max_speed: f32,
+    /// How fast the velocity reaches `max_speed` while moving
+    pub acceleration: f32,
+    /// How fast the velocity goes back to zero without input
+    pub deceleration: f32,
+    /// Fraction of acceleration and deceleration available when not
+    /// [`Grounded`] in platformer mode
+    pub air_control: f32,
+    /// Vertical speed given by a jump
+    pub jump_speed: f32,
+    /// Fraction of the upward speed kept when the jump is released early
+    pub jump_cut: f32,
+    /// A jump is still allowed this long after leaving the ground
+    pub coyote_time: f32,
+    /// A jump pressed this long before landing happens on landing
+    pub jump_buffer: f32,
+    /// Replaces the form gravity scale in platformer mode
+    pub platformer_gravity_scale: f32,
+}
+
+impl Default for MovementController {
+    fn default() -> Self {
+        Self {
+            mode: MovementMode::Flight,
+            max_speed: 600.0,
+            acceleration: 4000.0,
+            deceleration: 3000.0,
+            air_control: 0.5,
+            jump_speed: 900.0,
+            jump_cut: 0.4,
+            coyote_time: 0.1,
+            jump_buffer: 0.15,
+            platformer_gravity_scale: 3.0,
+        }
+    }
+}
+
+impl MovementController {
+    /// The gravity scale of the player given the one of its current form
+    pub fn gravity_scale(&self, form_gravity_scale: f32) -> f32 {
+        match self.mode {
+            MovementMode::Flight => form_gravity_scale,
+            MovementMode::Platformer => self.platformer_gravity_scale,
+        }
+    }
+}
+
+/// Marks a body standing on the terrain
+#[derive(Component)]
+pub struct Grounded;
+
+/// Timers of the platformer jump
+#[derive(Component, Default)]
+pub struct JumpState {
+    /// Seconds since the body was last grounded
+    pub airborne_time: f32,
+    /// Seconds left before a buffered jump is dropped
+    pub buffered: f32,
+    /// Set by a jump until the body starts falling or the jump is released
+    pub rising: bool,
+}
+
+fn toggle_movement_mode(
+    actions: Res<ButtonInput<Action>>,
+    player: Single<(&mut MovementController, &PlayerForms, &mut GravityScale), With<Player>>,
+) {
+    if !actions.just_pressed(Action::ToggleMovementMode) {
+        return;
+    }
+    let (mut controller, forms, mut gravity_scale) = player.into_inner();
+    controller.mode = match controller.mode {
+        MovementMode::Flight => MovementMode::Platformer,
+        MovementMode::Platformer => MovementMode::Flight,
+    };
+    gravity_scale.0 = controller.gravity_scale(forms.form().gravity_scale);
+    info!("movement mode: {:?}", controller.mode);
+}
+
+/// Jump presses are caught every frame, the fixed timestep may miss them
+fn buffer_jump(
+    actions: Res<ButtonInput<Action>>,
+    mut player: Query<(&MovementController, &mut JumpState), With<Player>>,
+) {
+    if !actions.just_pressed(Action::Jump) {
+        return;
+    }
+    for (controller, mut jump) in &mut player {
+        jump.buffered = controller.jump_buffer;
+    }
+}
+
+/// Cast the collider a few pixels down against the terrain, only surfaces
+/// facing up are ground
+fn detect_ground(
+    mut commands: Commands,
+    mut bodies: Query<(
+        Entity,
+        &Collider,
+        &GlobalTransform,
+        &mut JumpState,
+        Has<Grounded>,
+    )>,
+    terrain: Query<(), With<Terrain>>,
+    rapier_context: ReadRapierContext,
+    time: Res<Time>,
+) {
+    let Ok(context) = rapier_context.single() else {
+        return;
+    };
+    let is_terrain = |entity: Entity| terrain.contains(entity);
+    let filter = QueryFilter::default()
+        .exclude_sensors()
+        .predicate(&is_terrain);
+
+    for (entity, collider, transform, mut jump, was_grounded) in &mut bodies {
+        let (_, rotation, translation) = transform.to_scale_rotation_translation();
+        let grounded = context
+            .cast_shape(
+                translation.truncate(),
+                rotation.to_euler(EulerRot::ZYX).0,
+                Vec2::NEG_Y,
+                collider,
+                ShapeCastOptions {
+                    max_time_of_impact: GROUND_PROBE_DISTANCE,
+                    // touching a wall or a ceiling is a hit at time 0
+                    compute_impact_geometry_on_penetration: true,
+                    ..default()
+                },
+                filter,
+            )
+            .and_then(|(_, hit)| hit.details)
+            .is_some_and(|details| details.normal1.y > GROUND_MIN_NORMAL_Y);
+
+        if grounded {
+            jump.airborne_time = 0.0;
+        } else {
+            jump.airborne_time += time.delta_secs();
+        }
+        match (grounded, was_grounded) {
+            (true, false) => {
+                commands.entity(entity).insert(Grounded);
+            }
+            (false, true) => {
+                commands.entity(entity).remove::<Grounded>();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Push the velocity toward `direction * max_speed` with an impulse, the
+/// change per second never exceeds the controller rates
+fn move_player(
+    mut player: Query<
+        (
+            &MovementController,
+            &mut JumpState,
+            &Velocity,
+            &ReadMassProperties,
+            &mut ExternalImpulse,
+            Has<Grounded>,
+        ),
+        With<Player>,
+    >,
+    actions: Res<ButtonInput<Action>>,
+    sticks: Res<GamepadSticks>,
+    time: Res<Time>,
+) {
+    let Ok((controller, mut jump, velocity, mass, mut impulse, grounded)) = player.single_mut()
+    else {
+        return;
+    };
+    let mass = mass.get().mass;
+    if mass <= 0.0 {
+        return;
+    }
+    let delta_secs = time.delta_secs();
+
+    let mut direction = sticks.left;
+    if actions.pressed(Action::MoveUp) {
+        direction.y += 1.0;
+    }
+    if actions.pressed(Action::MoveDown) {
+        direction.y -= 1.0;
+    }
+    if actions.pressed(Action::MoveLeft) {
+        direction.x -= 1.0;
+    }
+    if actions.pressed(Action::MoveRight) {
+        direction.x += 1.0;
+    }
+    let direction = direction.clamp_length_max(1.0);
+
+    let mut rate = if direction == Vec2::ZERO {
+        controller.deceleration
+    } else {
+        controller.acceleration
+    };
+    // flying is always "in the air", air control only matters on the terrain
+    if controller.mode == MovementMode::Platformer && !grounded {
+        rate *= controller.air_control;
+    }
+
+    // several fixed steps can run before rapier applies the impulse,
+    // count the one already queued
+    let current = velocity.linvel + impulse.impulse / mass;
+    let mut change = match controller.mode {
+        MovementMode::Flight => {
+            (direction * controller.max_speed - current).clamp_length_max(rate * delta_secs)
+        }
+        MovementMode::Platformer => {
+            Vec2::X
+                * (direction.x * controller.max_speed - current.x)
+                    .clamp(-rate * delta_secs, rate * delta_secs)
+        }
+    };
+
+    if controller.mode == MovementMode::Platformer {
+        jump.buffered = (jump.buffered - delta_secs).max(0.0);
+        if jump.buffered > 0.0 && jump.airborne_time <= controller.coyote_time {
+            change.y = controller.jump_speed - current.y;
+            jump.buffered = 0.0;
+            // no second jump from the coyote time of this one
+            jump.airborne_time = controller.coyote_time + delta_secs;
+            jump.rising = true;
+        } else if jump.rising && (current.y <= 0.0 || !actions.pressed(Action::Jump)) {
+            // releasing early cuts the jump short
+            if current.y > 0.0 {
+                change.y = current.y * (controller.jump_cut - 1.0);
+            }
+            jump.rising = false;
+        }
+    }
+
+    impulse.impulse += change * mass;
+}
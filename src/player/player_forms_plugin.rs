@@ -0,0 +1,203 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::read_ron,
+    input::Action,
+    robot_constructor::{EntityColor, PartBlueprint, Shape},
+    shape_morph::{ShapeMorph, ShapeMorphSettings},
+};
+
+use super::{movement_plugin::MovementController, player_plugin::Player};
+
+/// Where the forms the player can take are read from at startup
+const FORMS_CONFIG_PATH: &str = "config/forms.ron";
+
+/// Cycle the player through its [`PlayerForms`] with the ChangeShape and
+/// PreviousShape actions
+pub struct PlayerFormsPlugin;
+impl Plugin for PlayerFormsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FormsConfig>()
+            .add_systems(Startup, load_forms_config)
+            .add_systems(Update, change_shape)
+            .add_observer(on_change_shape);
+    }
+}
+
+/// Everything that changes when the player takes another form
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PlayerForm {
+    pub name: String,
+    pub shape: Shape,
+    /// Mass added on top of the one computed from the collider
+    #[serde(default)]
+    pub mass: Option<f32>,
+    pub gravity_scale: f32,
+    pub friction: f32,
+    pub color: Color,
+}
+
+impl PlayerForm {
+    /// The form a robot part is spawned with
+    pub fn from_part(part: &PartBlueprint) -> Self {
+        Self {
+            name: part.name.clone(),
+            shape: part.shape.clone(),
+            mass: part.mass,
+            gravity_scale: part.gravity_scale,
+            friction: 0.5,
+            color: part.color,
+        }
+    }
+}
+
+/// The ordered list of forms of the player, `current` is the one in use
+#[derive(Component)]
+pub struct PlayerForms {
+    pub forms: Vec<PlayerForm>,
+    pub current: usize,
+}
+
+impl PlayerForms {
+    /// `first` followed by the configured forms
+    pub fn new(first: PlayerForm, config: &FormsConfig) -> Self {
+        let mut forms = vec![first];
+        forms.extend(config.forms.iter().cloned());
+        Self { forms, current: 0 }
+    }
+
+    pub fn form(&self) -> &PlayerForm {
+        &self.forms[self.current]
+    }
+}
+
+/// The forms the player can take after the one of its robot root part
+#[derive(Resource, Serialize, Deserialize)]
+pub struct FormsConfig {
+    pub forms: Vec<PlayerForm>,
+}
+
+impl Default for FormsConfig {
+    fn default() -> Self {
+        Self {
+            forms: vec![
+                PlayerForm {
+                    name: "ball".to_string(),
+                    shape: Shape::Circle { radius: 30.0 },
+                    mass: None,
+                    gravity_scale: -3.0,
+                    friction: 0.2,
+                    color: Color::linear_rgb(1.0, 0.6, 0.1),
+                },
+                PlayerForm {
+                    name: "capsule".to_string(),
+                    shape: Shape::Capsule {
+                        half_length: 25.0,
+                        radius: 18.0,
+                    },
+                    mass: None,
+                    gravity_scale: -3.0,
+                    friction: 1.0,
+                    color: Color::linear_rgb(0.8, 0.2, 1.0),
+                },
+                PlayerForm {
+                    name: "heavy hexagon".to_string(),
+                    shape: Shape::RegularPolygon {
+                        sides: 6,
+                        radius: 35.0,
+                    },
+                    mass: Some(500.0),
+                    gravity_scale: 1.0,
+                    friction: 0.8,
+                    color: Color::linear_rgb(0.6, 0.6, 0.7),
+                },
+            ],
+        }
+    }
+}
+
+fn load_forms_config(mut commands: Commands) {
+    match read_ron::<FormsConfig>(FORMS_CONFIG_PATH) {
+        Ok(config) => commands.insert_resource(config),
+        Err(err) => warn!("{err}, using the default forms"),
+    }
+}
+
+/// Triggered on the player to move `step` forms forward (backward if
+/// negative) in its [`PlayerForms`], 0 applies the current form again
+#[derive(Event)]
+pub struct ChangeShape {
+    pub step: isize,
+}
+
+/// Triggered on the player once it took a new form
+#[derive(Event)]
+pub struct PlayerFormChanged {
+    pub index: usize,
+    pub name: String,
+}
+
+fn change_shape(
+    mut commands: Commands,
+    actions: Res<ButtonInput<Action>>,
+    player: Query<Entity, (With<Player>, Without<ShapeMorph>)>,
+) {
+    let step = match (
+        actions.just_pressed(Action::ChangeShape),
+        actions.just_pressed(Action::PreviousShape),
+    ) {
+        (true, false) => 1,
+        (false, true) => -1,
+        _ => return,
+    };
+    if let Ok(entity) = player.single() {
+        commands.trigger_targets(ChangeShape { step }, entity);
+    }
+}
+
+/// Apply the selected form, the shape grows through a [`ShapeMorph`]
+/// unless the form is applied again
+fn on_change_shape(
+    trigger: Trigger<ChangeShape>,
+    mut player: Query<(&mut PlayerForms, &Shape, Option<&MovementController>)>,
+    morph_settings: Res<ShapeMorphSettings>,
+    mut commands: Commands,
+) {
+    let entity = trigger.target();
+    let Ok((mut forms, shape, controller)) = player.get_mut(entity) else {
+        return;
+    };
+    let len = forms.forms.len() as isize;
+    forms.current = (forms.current as isize + trigger.step).rem_euclid(len) as usize;
+    let form = forms.form().clone();
+
+    let mut player_commands = commands.entity(entity);
+    if trigger.step == 0 {
+        player_commands.insert(form.shape.clone());
+    } else {
+        player_commands.insert(ShapeMorph::new(
+            shape.clone(),
+            form.shape.clone(),
+            morph_settings.duration,
+        ));
+    }
+    player_commands.insert((
+        GravityScale(controller.map_or(form.gravity_scale, |controller| {
+            controller.gravity_scale(form.gravity_scale)
+        })),
+        Friction::coefficient(form.friction),
+        EntityColor(form.color),
+    ));
+    match form.mass {
+        Some(mass) => player_commands.insert(AdditionalMassProperties::Mass(mass)),
+        None => player_commands.remove::<AdditionalMassProperties>(),
+    };
+
+    info!("player form: {}", form.name);
+    player_commands.trigger(PlayerFormChanged {
+        index: forms.current,
+        name: form.name,
+    });
+}
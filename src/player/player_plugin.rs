@@ -6,153 +6,266 @@ use bevy_rapier2d::prelude::*;
 use rand::random_range;
 
 use crate::{
-    cube::Cube,
-    robot_constructor::{spawn_robot, EntityColor, Shape},
-    MyTimer, Terrain,
+    cube::{Cube, ShapePalette},
+    input::Action,
+    joint::{spawn_joint, Joint, JointSelection},
+    robot_constructor::{despawn_robot, spawn_robot, EntityColor, RobotBlueprint, RobotPart},
+    terrain::{
+        destruction_plugin::{DigTerrain, TerrainDestruction},
+        terrain_plugin::{Crater, Terrain},
+    },
+    MyTimer,
 };
 
-use super::{mouse_interaction_plugin::MouseInteractionPlugin, resources::PlayerMouseCoor};
-
-/// Player movement speed factor.
-const PLAYER_SPEED: f32 = 10_000.;
-
-#[derive(Event)]
-struct ChangeShape;
+use super::{
+    join_selection_plugin::JoinSelectionPlugin,
+    joint_edit_plugin::JointEditPlugin,
+    lifecycle_plugin::{PlayerLifecyclePlugin, PlayerRespawn, PlayerSpawned},
+    mouse_interaction_plugin::{update_virtual_cursor, MouseInteractionPlugin},
+    movement_plugin::{JumpState, MovementController, MovementPlugin},
+    player_forms_plugin::{ChangeShape, FormsConfig, PlayerForm, PlayerForms, PlayerFormsPlugin},
+    resources::{PlayerMouseCoor, PlayerSpawnPoint, VirtualCursor},
+};
 
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut bevy::app::App) {
-        app.add_plugins(MouseInteractionPlugin)
-            .add_systems(Startup, spawn_player)
-            .add_systems(Update, update_mouse_player_coor)
-            .add_systems(FixedUpdate, (change_shape, move_player).chain())
-            .add_systems(Update, (spawn_cube_skill, despawn_cube_skill))
-            .add_observer(connect_entities);
+        app.add_plugins((
+            MouseInteractionPlugin,
+            JointEditPlugin,
+            JoinSelectionPlugin,
+            PlayerFormsPlugin,
+            MovementPlugin,
+            PlayerLifecyclePlugin,
+        ))
+        .init_resource::<PlayerSpawnPoint>()
+        .add_systems(Startup, load_player_blueprint)
+        .add_systems(Update, (reload_player_robot, spawn_player).chain())
+        .add_systems(
+            Update,
+            update_mouse_player_coor.after(update_virtual_cursor),
+        )
+        .add_systems(
+            Update,
+            (
+                toggle_click_mode,
+                spawn_cube_skill,
+                despawn_cube_skill,
+                dig_skill,
+                join_cube,
+            ),
+        )
+        .add_observer(connect_entities);
     }
 }
 
 #[derive(Component)]
 pub struct Player(pub ClickMode);
 
-/// Button state for the system SpawnCube -> on_cube_spawn and join_cube observer
+/// Button state for the system SpawnCube -> on_cube_spawn, join_cube,
+/// the JointEditPlugin, the TileEditorPlugin and the CubeChainPlugin
 #[derive(Clone, Copy, PartialEq)]
 pub enum ClickMode {
     SpawnCube,
     JoinCube,
+    EditJoint,
+    PaintTerrain,
+    BuildChain,
+}
+
+impl ClickMode {
+    pub fn next(self) -> Self {
+        match self {
+            ClickMode::SpawnCube => ClickMode::JoinCube,
+            ClickMode::JoinCube => ClickMode::EditJoint,
+            ClickMode::EditJoint => ClickMode::PaintTerrain,
+            ClickMode::PaintTerrain => ClickMode::BuildChain,
+            ClickMode::BuildChain => ClickMode::SpawnCube,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ClickMode::SpawnCube => "SpawnCube",
+            ClickMode::JoinCube => "JoinCube",
+            ClickMode::EditJoint => "EditJoint",
+            ClickMode::PaintTerrain => "PaintTerrain",
+            ClickMode::BuildChain => "BuildChain",
+        }
+    }
 }
 
 /// A component for storing 2 Entity and allow to connect them with a rapier joint
 #[derive(Default, Component)]
-pub struct PairEntitySelection(pub (Option<Entity>, Option<Entity>));
+pub struct PairEntitySelection(pub (Option<SelectedBody>, Option<SelectedBody>));
+
+/// An entity clicked in JoinCube mode and where it was clicked
+#[derive(Clone, Copy, Debug)]
+pub struct SelectedBody {
+    pub entity: Entity,
+    /// click position in the entity local coord, used as joint anchor
+    pub anchor: Vec2,
+}
+
+/// Handle of the blueprint the player robot is built from
+#[derive(Resource)]
+struct PlayerBlueprint(Handle<RobotBlueprint>);
 
-/// Make the player of another form
-fn change_shape(
+fn load_player_blueprint(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(PlayerBlueprint(
+        asset_server.load("robots/player.robot.ron"),
+    ));
+}
+
+/// Spawn the player robot as soon as its blueprint is loaded and whenever it
+/// is missing after the respawn delay, falling back to the built-in robot if
+/// the file cannot be loaded
+#[allow(clippy::too_many_arguments)]
+fn spawn_player(
     mut commands: Commands,
-    kb_input: Res<ButtonInput<KeyCode>>,
-    entity_query: Query<(Entity, &Shape), (With<Player>, With<Shape>)>,
+    player: Query<(), With<Player>>,
+    player_blueprint: Res<PlayerBlueprint>,
+    blueprints: Res<Assets<RobotBlueprint>>,
+    asset_server: Res<AssetServer>,
+    spawn_point: Res<PlayerSpawnPoint>,
+    respawn: Res<PlayerRespawn>,
+    forms: Res<FormsConfig>,
 ) {
-    if kb_input.just_released(KeyCode::KeyG) {
-        if let Ok((entity, old_shape)) = entity_query.single() {
-            let new_shape = match old_shape {
-                Shape::Circle { radius } => Shape::Rectangle {
-                    height: *radius,
-                    width: *radius,
-                },
-                Shape::Rectangle { width, height: _ } => Shape::Circle { radius: *width },
-            };
-            commands.entity(entity).insert(new_shape);
-        }
+    if !player.is_empty() || !respawn.ready() {
+        return;
     }
-}
+    let fallback;
+    let blueprint = match blueprints.get(&player_blueprint.0) {
+        Some(blueprint) => blueprint,
+        None if asset_server.load_state(&player_blueprint.0).is_failed() => {
+            fallback = RobotBlueprint::default();
+            &fallback
+        }
+        None => return,
+    };
 
-fn spawn_player(mut commands: Commands) {
-    let pl_id = spawn_robot(&mut commands);
+    let pl_id = spawn_robot(&mut commands, blueprint, spawn_point.0);
+    let root = blueprint
+        .part(&blueprint.root)
+        .expect("robot blueprints are validated");
     // la prima volta che viene aggiunta un Shape viene
     // contata come un evento ChangeShape
     commands
         .entity(pl_id)
         .insert(Player(ClickMode::SpawnCube))
         .insert(PairEntitySelection::default())
-        .trigger(ChangeShape);
+        .insert(PlayerForms::new(PlayerForm::from_part(root), &forms))
+        .insert((
+            MovementController::default(),
+            JumpState::default(),
+            ExternalImpulse::default(),
+            ReadMassProperties::default(),
+        ))
+        .trigger(ChangeShape { step: 0 });
+    commands.trigger(PlayerSpawned { player: pl_id });
 }
 
-fn move_player(
-    mut velocity: Query<&mut Velocity, With<Player>>,
-    kb_input: Res<ButtonInput<KeyCode>>,
-    time: Res<Time>,
-    _spawn_timer: Res<MyTimer>,
+/// Despawn the player robot when its blueprint file changes,
+/// spawn_player rebuilds it right after with the new layout
+fn reload_player_robot(
+    mut events: EventReader<AssetEvent<RobotBlueprint>>,
+    player_blueprint: Res<PlayerBlueprint>,
+    player: Query<Entity, With<Player>>,
+    parts: Query<(Entity, &RobotPart)>,
+    mut commands: Commands,
 ) {
-    //println!("timer: {}", spawn_timer.0.elapsed_secs());
-    let delta_secs = time.delta_secs();
-    //spawn_timer.0.tick(Duration::from_secs_f32(delta_secs));
-    let mut vel = velocity.single_mut().unwrap();
-
-    if kb_input.pressed(KeyCode::KeyW) {
-        vel.linvel.y += PLAYER_SPEED * delta_secs;
-    }
-    if kb_input.pressed(KeyCode::KeyS) {
-        vel.linvel.y -= PLAYER_SPEED * delta_secs;
-    }
-    if kb_input.pressed(KeyCode::KeyA) {
-        vel.linvel.x -= PLAYER_SPEED * delta_secs;
+    if !events
+        .read()
+        .any(|event| event.is_modified(&player_blueprint.0))
+    {
+        return;
     }
-    if kb_input.pressed(KeyCode::KeyD) {
-        vel.linvel.x += PLAYER_SPEED * delta_secs;
+    for player in &player {
+        despawn_robot(&mut commands, player, &parts);
     }
-
-    vel.linvel = vel.linvel.lerp(Vec2::ZERO, 0.2);
 }
 
 fn update_mouse_player_coor(
     windows: Query<&Window>,
     camera_q: Query<(&Camera, &GlobalTransform), With<Camera>>,
+    virtual_cursor: Res<VirtualCursor>,
     mut mouse_coor: ResMut<PlayerMouseCoor>,
 ) {
-    let window = windows.single();
-    let (camera, camera_transform) = camera_q.single().unwrap();
+    let (Ok(window), Ok((camera, camera_transform))) = (windows.single(), camera_q.single()) else {
+        return;
+    };
+    let cursor = if virtual_cursor.active {
+        Some(virtual_cursor.position)
+    } else {
+        window.cursor_position()
+    };
 
-    if let Some(world_position) = window
-        .unwrap()
-        .cursor_position()
-        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
+    if let Some(world_position) =
+        cursor.and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
     {
         //eprintln!("World coords: {}/{}", world_position.x, world_position.y);
 
         mouse_coor.update(world_position.x, world_position.y)
     }
 }
+fn toggle_click_mode(actions: Res<ButtonInput<Action>>, mut player: Single<&mut Player>) {
+    if actions.just_pressed(Action::ToggleClickMode) {
+        player.0 = player.0.next();
+    }
+}
+
 fn despawn_cube_skill(
     mut commands: Commands,
-    query: Query<(Entity, &Transform, &Cube), Without<Terrain>>,
+    cubes: Query<(), (With<Cube>, Without<Terrain>)>,
     mouse_coor: Res<PlayerMouseCoor>,
-    mouse_input: Res<ButtonInput<MouseButton>>,
+    actions: Res<ButtonInput<Action>>,
+    player: Single<&Player>,
+    rapier_context: ReadRapierContext,
 ) {
-    if mouse_input.pressed(MouseButton::Right) {
-        //println!("right");
-        let mouse_pos: Vec2 = mouse_coor.into_inner().into();
-
-        if let Some((entity, _, _)) = query.iter().find(|(_, trans, cube)| {
-            let trans = trans.translation;
-            let cube_pos = Vec2 {
-                x: trans.x,
-                y: trans.y,
-            };
-            let distance = mouse_pos.distance(cube_pos).abs();
-            //println!("{distance}");
-            distance < cube.height / 2.0
-        }) {
-            commands.entity(entity).despawn();
-        }
+    // right click cancels the selection in JoinCube and erases tiles in PaintTerrain
+    if matches!(player.0, ClickMode::JoinCube | ClickMode::PaintTerrain) {
+        return;
+    }
+    if !actions.pressed(Action::DeleteCube) {
+        return;
+    }
+    let Ok(context) = rapier_context.single() else {
+        return;
+    };
+    let mouse_pos: Vec2 = mouse_coor.into_inner().into();
+    if let Some(entity) = pick_body(&context, mouse_pos, |entity| cubes.contains(entity)) {
+        commands.entity(entity).despawn();
     }
 }
+/// Dig the terrain under the cursor, only within reach of the player
+fn dig_skill(
+    mouse_coor: Res<PlayerMouseCoor>,
+    actions: Res<ButtonInput<Action>>,
+    destruction: Res<TerrainDestruction>,
+    player: Single<&GlobalTransform, With<Player>>,
+    mut dig: EventWriter<DigTerrain>,
+) {
+    if !actions.just_pressed(Action::Dig) {
+        return;
+    }
+    let center: Vec2 = mouse_coor.into_inner().into();
+    if center.distance(player.translation().truncate()) > destruction.dig_reach {
+        return;
+    }
+    dig.write(DigTerrain(Crater {
+        center,
+        radius: destruction.dig_radius,
+    }));
+}
+
 fn spawn_cube_skill(
     mut commands: Commands,
     mouse_coor: Res<PlayerMouseCoor>,
-    mouse_input: Res<ButtonInput<MouseButton>>,
-    _kbd_input: Res<ButtonInput<KeyCode>>,
+    actions: Res<ButtonInput<Action>>,
     mut spawn_timer: ResMut<MyTimer>,
     player: Single<&Player>,
+    palette: Res<ShapePalette>,
     time: Res<Time>,
 ) {
     let click_mode = player.0;
@@ -161,23 +274,18 @@ fn spawn_cube_skill(
 
         spawn_timer.0.tick(Duration::from_secs_f32(delta_time));
 
-        if mouse_input.pressed(MouseButton::Left)
+        if actions.pressed(Action::SpawnCube)
             //&& kbd_input.pressed(KeyCode::KeyP)
             && spawn_timer.0.finished()
         {
             spawn_timer.0.reset();
             let (x_spawn, y_spawn) = (mouse_coor.x, mouse_coor.y);
             let rng = || random_range(0.0..1.0);
-            commands
-                .spawn(Cube::bundle(30.0, 30.0))
-                .insert((
-                    EntityColor(Color::linear_rgb(rng(), rng(), rng())),
-                    Transform::from_xyz(x_spawn, y_spawn, 0.0),
-                    Shape::Circle { radius: 20.0 },
-                    RigidBody::Dynamic,
-                    Velocity::zero(),
-                ))
-                .observe(join_cube);
+            commands.spawn(Cube::with_shape(palette.shape())).insert((
+                EntityColor(Color::linear_rgb(rng(), rng(), rng())),
+                Transform::from_xyz(x_spawn, y_spawn, 0.0),
+                Velocity::zero(),
+            ));
             //.observe(on_cube_spawn) attacca un local observer all'entity
             //commands.trigger_targets(SpawnedAcube, id);
 
@@ -191,40 +299,108 @@ fn spawn_cube_skill(
 #[derive(Event)]
 struct ReadyToConnect;
 
-pub fn join_cube(
-    trig: Trigger<Pointer<Click>>,
-    mut selected_pair_entity: Single<&mut PairEntitySelection, With<Player>>,
+/// JoinCube: SpawnCube picks the cube or robot part under the cursor, the
+/// virtual cursor works as well as the mouse
+fn join_cube(
+    actions: Res<ButtonInput<Action>>,
+    mouse_coor: Res<PlayerMouseCoor>,
+    player: Single<(&Player, &mut PairEntitySelection)>,
+    joinable: Query<&GlobalTransform, Or<(With<Cube>, With<RobotPart>)>>,
+    joints: Query<&Joint>,
+    rapier_context: ReadRapierContext,
     mut commands: Commands,
 ) {
-    let selected_entity = trig.target();
-    let (ent1, ent2) = selected_pair_entity.0;
-    let mut ents = [ent1, ent2];
-    if let Some((n, _)) = ents
-        .into_iter()
-        .enumerate()
-        .find(|(_n, x)| matches!(x, None))
-    {
-        ents[n] = Some(selected_entity);
-        dbg!(&ents);
-        selected_pair_entity.0 = (ents[0], ents[1]);
-    } else {
-        commands.trigger(ReadyToConnect);
+    let (player, mut selected_pair_entity) = player.into_inner();
+    if player.0 != ClickMode::JoinCube || !actions.just_pressed(Action::SpawnCube) {
+        return;
     }
+    let Ok(context) = rapier_context.single() else {
+        return;
+    };
+    let cursor: Vec2 = mouse_coor.into_inner().into();
+    let Some(selected_entity) = pick_body(&context, cursor, |entity| joinable.contains(entity))
+    else {
+        return;
+    };
+    // anchor in the body local coord
+    let anchor = joinable
+        .get(selected_entity)
+        .map(|transform| {
+            transform
+                .affine()
+                .inverse()
+                .transform_point3(cursor.extend(0.0))
+                .truncate()
+        })
+        .unwrap_or_default();
+    let selected = SelectedBody {
+        entity: selected_entity,
+        anchor,
+    };
+
+    match selected_pair_entity.0 {
+        (None, _) => selected_pair_entity.0 = (Some(selected), None),
+        // the first body was despawned since it was selected, start over
+        (Some(first), _) if !joinable.contains(first.entity) => {
+            selected_pair_entity.0 = (Some(selected), None);
+        }
+        (Some(first), _) if first.entity == selected.entity => {
+            info!("cannot join an entity to itself");
+        }
+        (Some(first), _) if already_joined(first.entity, selected.entity, &joints) => {
+            info!("entities are already joined");
+        }
+        (Some(first), _) => {
+            selected_pair_entity.0 = (Some(first), Some(selected));
+            commands.trigger(ReadyToConnect);
+        }
+    }
+}
+
+/// The first entity accepted by `is_body` whose collider contains `point`,
+/// picks the real collider shape instead of a distance to its center
+pub fn pick_body(
+    context: &RapierContext,
+    point: Vec2,
+    is_body: impl Fn(Entity) -> bool,
+) -> Option<Entity> {
+    let filter = QueryFilter::default().exclude_sensors().predicate(&is_body);
+    let mut picked = None;
+    context.intersections_with_point(point, filter, |entity| {
+        picked = Some(entity);
+        false
+    });
+    picked
+}
+
+fn already_joined(ent1: Entity, ent2: Entity, joints: &Query<&Joint>) -> bool {
+    joints.iter().any(|joint| {
+        (joint.body1 == ent1 && joint.body2 == ent2) || (joint.body1 == ent2 && joint.body2 == ent1)
+    })
 }
 
 fn connect_entities(
     _trig: Trigger<ReadyToConnect>,
     mut selected_entities: Query<&mut PairEntitySelection, With<Player>>,
+    bodies: Query<(), With<RigidBody>>,
+    joint_selection: Res<JointSelection>,
     mut commands: Commands,
 ) {
-    let mut pair_entity = selected_entities.single_mut().unwrap();
-    let (ent1, ent2) = pair_entity.0;
-    let ent1 = ent1.unwrap();
-    let ent2 = ent2.unwrap();
-    let joint = RopeJointBuilder::new(80.0)
-        .local_anchor1(Vec2 { x: 0.0, y: 0.0 })
-        .local_anchor2(Vec2 { x: 0.0, y: 0.0 });
-    commands.entity(ent1).insert(ImpulseJoint::new(ent2, joint));
+    // the player may have died since the selection was made
+    let Ok(mut pair_entity) = selected_entities.single_mut() else {
+        return;
+    };
+    let (Some(ent1), Some(ent2)) = pair_entity.0 else {
+        return;
+    };
+    // a joint towards a despawned body would never be cleaned up
+    if !bodies.contains(ent1.entity) || !bodies.contains(ent2.entity) {
+        *pair_entity = PairEntitySelection::default();
+        return;
+    }
+    // ent2 is the rapier parent (body1) of the joint spawned under ent1 (body2)
+    let joint = joint_selection.spec(ent2.anchor, ent1.anchor);
+    spawn_joint(&mut commands, ent2.entity, ent1.entity, joint);
 
     *pair_entity = PairEntitySelection((None, None));
 }
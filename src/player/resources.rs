@@ -5,3 +5,15 @@ pub struct PlayerMouseCoor {
     pub x: f32,
     pub y: f32,
 }
+
+/// A cursor moved by the right stick of the gamepad, in viewport coord like
+/// `Window::cursor_position`. It replaces the mouse until the mouse moves again
+#[derive(Resource, Default)]
+pub struct VirtualCursor {
+    pub position: Vec2,
+    pub active: bool,
+}
+
+/// Where the player robot is spawned, set by the level
+#[derive(Resource, Default)]
+pub struct PlayerSpawnPoint(pub Vec2);
@@ -1,16 +1,30 @@
-use bevy::prelude::*;
+use std::collections::HashMap;
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext, RenderAssetUsages},
+    prelude::*,
+    render::mesh::{Indices, PrimitiveTopology},
+};
 use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
-use crate::player::player_plugin::join_cube;
+use crate::{
+    config::{read_ron_asset, RonFileError},
+    joint::{spawn_joint, JointKind, JointSpec},
+};
 
 /// A plugin which automatically attach a bevy_rapier2d Mesh2d and MeshMaterial2d matching the shape
-/// using an observer which wait for OnInsert of a crate::Shape component
+/// using an observer which wait for OnInsert of a crate::Shape component.
+/// It also registers the [`RobotBlueprint`] asset, loaded from `assets/robots/*.robot.ron`
 pub struct RobotConstructorPlugin;
 impl Plugin for RobotConstructorPlugin {
     fn build(&self, app: &mut App) {
-        app.add_observer(on_insert_shape_attach_mesh_and_material)
+        app.init_asset::<RobotBlueprint>()
+            .init_asset_loader::<RobotBlueprintLoader>()
+            .add_observer(on_insert_shape_attach_mesh_and_material)
             .add_observer(
-                |trigger: Trigger<OnAdd, EntityColor>,
+                |trigger: Trigger<OnInsert, EntityColor>,
                  color: Query<&EntityColor>,
                  mut commands: Commands,
                  mut materials: ResMut<Assets<ColorMaterial>>| {
@@ -24,10 +38,283 @@ impl Plugin for RobotConstructorPlugin {
     }
 }
 
-#[derive(Component, Clone, Copy)]
+/// The rigid body shape of an entity, inserting it attaches the matching
+/// Mesh2d and Collider.
+///
+/// Measures follow bevy_rapier, that is half extents: `Rectangle` takes half
+/// width and half height, `Capsule` the half length of its straight segment
+/// (vertical, excluding the caps). `RegularPolygon` and `Circle` take the
+/// circumradius. Points of `Triangle` and `ConvexPolygon` are in local coord.
+///
+/// `Compound` glues several shapes into a single rigid body, e.g. an L-piece:
+/// ```ron
+/// Compound([
+///     (shape: Rectangle(width: 30.0, height: 8.0)),
+///     (shape: Rectangle(width: 8.0, height: 20.0), offset: (-22.0, 28.0)),
+/// ])
+/// ```
+#[derive(Component, Clone, Serialize, Deserialize)]
 pub enum Shape {
-    Circle { radius: f32 },
-    Rectangle { width: f32, height: f32 },
+    Circle {
+        radius: f32,
+    },
+    Rectangle {
+        width: f32,
+        height: f32,
+    },
+    Capsule {
+        half_length: f32,
+        radius: f32,
+    },
+    Triangle {
+        a: Vec2,
+        b: Vec2,
+        c: Vec2,
+    },
+    RegularPolygon {
+        sides: u32,
+        radius: f32,
+    },
+    /// Any set of points, the body is their convex hull
+    ConvexPolygon(Vec<Vec2>),
+    /// Several shapes moved by their offset and rotation, nested compounds
+    /// are flattened since rapier does not support them
+    Compound(Vec<ShapePart>),
+}
+
+/// One of the shapes of a [`Shape::Compound`]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ShapePart {
+    pub shape: Shape,
+    #[serde(default)]
+    pub offset: Vec2,
+    /// radians, counter clockwise
+    #[serde(default)]
+    pub rotation: f32,
+}
+
+impl ShapePart {
+    fn transform(&self) -> Transform {
+        Transform::from_translation(self.offset.extend(0.0))
+            .with_rotation(Quat::from_rotation_z(self.rotation))
+    }
+}
+
+impl Shape {
+    /// Full size of the bounding box
+    pub fn size(&self) -> Vec2 {
+        let (min, max) = self.bounds();
+        max - min
+    }
+
+    /// Min and max corner of the bounding box in local coord
+    fn bounds(&self) -> (Vec2, Vec2) {
+        let half_size = match self {
+            Shape::Circle { radius } | Shape::RegularPolygon { radius, .. } => Vec2::splat(*radius),
+            Shape::Rectangle { width, height } => Vec2::new(*width, *height),
+            Shape::Capsule {
+                half_length,
+                radius,
+            } => Vec2::new(*radius, half_length + radius),
+            Shape::Triangle { a, b, c } => return points_bounds(&[*a, *b, *c]),
+            Shape::ConvexPolygon(points) => return points_bounds(points),
+            Shape::Compound(parts) => {
+                let corners: Vec<Vec2> = parts
+                    .iter()
+                    .flat_map(|part| {
+                        let (min, max) = part.shape.bounds();
+                        let rotation = Rot2::radians(part.rotation);
+                        [min, Vec2::new(min.x, max.y), max, Vec2::new(max.x, min.y)]
+                            .map(|corner| part.offset + rotation * corner)
+                    })
+                    .collect();
+                return points_bounds(&corners);
+            }
+        };
+        (-half_size, half_size)
+    }
+
+    /// Every non compound shape with its transform relative to `self`
+    fn leaves(&self) -> Vec<(Transform, &Shape)> {
+        match self {
+            Shape::Compound(parts) => parts
+                .iter()
+                .flat_map(|part| {
+                    let transform = part.transform();
+                    part.shape
+                        .leaves()
+                        .into_iter()
+                        .map(move |(leaf, shape)| (transform * leaf, shape))
+                })
+                .collect(),
+            shape => vec![(Transform::IDENTITY, shape)],
+        }
+    }
+
+    pub fn mesh(&self) -> Mesh {
+        match self {
+            Shape::Circle { radius } => Circle::new(*radius).into(),
+            // multiplication by 2.0 is necessary in bevy_rapier
+            // since it takes half measurement, avian instead use whole measurment
+            Shape::Rectangle { width, height } => Rectangle::new(width * 2.0, height * 2.0).into(),
+            Shape::Capsule {
+                half_length,
+                radius,
+            } => Capsule2d {
+                radius: *radius,
+                half_length: *half_length,
+            }
+            .into(),
+            Shape::Triangle { a, b, c } => Triangle2d::new(*a, *b, *c).into(),
+            Shape::RegularPolygon { sides, radius } => {
+                RegularPolygon::new(*radius, (*sides).max(3)).into()
+            }
+            Shape::ConvexPolygon(_) => {
+                // the mesh is built from the hull so that it matches the collider
+                let hull: Vec<Vec2> = self
+                    .collider()
+                    .as_convex_polygon()
+                    .map(|polygon| polygon.points().collect())
+                    .unwrap_or_default();
+                convex_polygon_mesh(&hull)
+            }
+            Shape::Compound(_) => {
+                let mut mesh = Mesh::new(
+                    PrimitiveTopology::TriangleList,
+                    RenderAssetUsages::default(),
+                )
+                .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, Vec::<[f32; 3]>::new())
+                .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, Vec::<[f32; 3]>::new())
+                .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, Vec::<[f32; 2]>::new())
+                .with_inserted_indices(Indices::U32(Vec::new()));
+                for (transform, shape) in self.leaves() {
+                    if let Err(err) = mesh.merge(&shape.mesh().transformed_by(transform)) {
+                        warn!("could not merge compound shape mesh: {err}");
+                    }
+                }
+                mesh
+            }
+        }
+    }
+
+    pub fn collider(&self) -> Collider {
+        match self {
+            Shape::Circle { radius } => Collider::ball(*radius),
+            Shape::Rectangle { width, height } => Collider::cuboid(*width, *height),
+            Shape::Capsule {
+                half_length,
+                radius,
+            } => Collider::capsule_y(*half_length, *radius),
+            Shape::Triangle { a, b, c } => Collider::triangle(*a, *b, *c),
+            Shape::RegularPolygon { sides, radius } => {
+                let polygon = RegularPolygon::new(*radius, (*sides).max(3));
+                let points: Vec<Vec2> = polygon.vertices(0.0).into_iter().collect();
+                convex_hull_collider(&points)
+            }
+            Shape::ConvexPolygon(points) => convex_hull_collider(points),
+            Shape::Compound(_) => {
+                let leaves = self.leaves();
+                if leaves.is_empty() {
+                    warn!("empty compound shape, using a ball collider");
+                    return Collider::ball(1.0);
+                }
+                Collider::compound(
+                    leaves
+                        .into_iter()
+                        .map(|(transform, shape)| {
+                            (
+                                transform.translation.truncate(),
+                                transform.rotation.to_euler(EulerRot::ZYX).0,
+                                shape.collider(),
+                            )
+                        })
+                        .collect(),
+                )
+            }
+        }
+    }
+
+    /// The same shape with every measure multiplied by `factor`
+    pub fn scaled(&self, factor: f32) -> Shape {
+        match self {
+            Shape::Circle { radius } => Shape::Circle {
+                radius: radius * factor,
+            },
+            Shape::Rectangle { width, height } => Shape::Rectangle {
+                width: width * factor,
+                height: height * factor,
+            },
+            Shape::Capsule {
+                half_length,
+                radius,
+            } => Shape::Capsule {
+                half_length: half_length * factor,
+                radius: radius * factor,
+            },
+            Shape::Triangle { a, b, c } => Shape::Triangle {
+                a: a * factor,
+                b: b * factor,
+                c: c * factor,
+            },
+            Shape::RegularPolygon { sides, radius } => Shape::RegularPolygon {
+                sides: *sides,
+                radius: radius * factor,
+            },
+            Shape::ConvexPolygon(points) => {
+                Shape::ConvexPolygon(points.iter().map(|point| point * factor).collect())
+            }
+            Shape::Compound(parts) => Shape::Compound(
+                parts
+                    .iter()
+                    .map(|part| ShapePart {
+                        shape: part.shape.scaled(factor),
+                        offset: part.offset * factor,
+                        rotation: part.rotation,
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+fn points_bounds(points: &[Vec2]) -> (Vec2, Vec2) {
+    let min = points.iter().copied().reduce(Vec2::min).unwrap_or_default();
+    let max = points.iter().copied().reduce(Vec2::max).unwrap_or_default();
+    (min, max)
+}
+
+/// Degenerate point sets (less than 3 points, all aligned) fall back to a
+/// tiny ball instead of panicking
+fn convex_hull_collider(points: &[Vec2]) -> Collider {
+    Collider::convex_hull(points).unwrap_or_else(|| {
+        warn!("degenerate convex polygon, using a ball collider");
+        Collider::ball(1.0)
+    })
+}
+
+/// Triangle fan over the counter clockwise vertices of a convex polygon
+fn convex_polygon_mesh(points: &[Vec2]) -> Mesh {
+    let positions: Vec<[f32; 3]> = points.iter().map(|p| [p.x, p.y, 0.0]).collect();
+    let (min, max) = points_bounds(points);
+    let size = (max - min).max(Vec2::splat(f32::EPSILON));
+    let uvs: Vec<[f32; 2]> = points
+        .iter()
+        .map(|p| {
+            let uv = (*p - min) / size;
+            [uv.x, 1.0 - uv.y]
+        })
+        .collect();
+    let indices: Vec<u32> = (1..points.len().saturating_sub(1) as u32)
+        .flat_map(|i| [0, i, i + 1])
+        .collect();
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 0.0, 1.0]; points.len()])
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+    .with_inserted_indices(Indices::U32(indices))
 }
 
 pub fn on_insert_shape_attach_mesh_and_material(
@@ -41,68 +328,229 @@ pub fn on_insert_shape_attach_mesh_and_material(
         return;
     };
 
-    let mesh_bundle = match *shape {
-        Shape::Circle { radius } => (
-            Mesh2d(meshes.add(Circle::new(radius))),
-            Collider::ball(radius),
-        ),
-        Shape::Rectangle { width, height } => (
-            Mesh2d(meshes.add(Rectangle::new(width * 2.0, height * 2.0))), // multiplication by 2.0 is necessary in bevy_rapier
-            // since it takes half measurement, avian instead use whole measurment
-            Collider::cuboid(width, height),
-        ),
-    };
-    commands.entity(entity).insert((mesh_bundle,));
+    commands
+        .entity(entity)
+        .insert((Mesh2d(meshes.add(shape.mesh())), shape.collider()));
 }
 
-pub fn spawn_robot(commands: &mut Commands) -> Entity {
-    let (height, width) = (40.0, 30.0);
-    let ball_radius = 60.0;
+/// Serializable description of a robot: a set of named rigid body parts
+/// and the joints connecting them.
+///
+/// ```ron
+/// (
+///     root: "head",
+///     parts: [
+///         (name: "head", shape: Rectangle(width: 30.0, height: 40.0), color: LinearRgba((red: 0.1, green: 1.0, blue: 0.3, alpha: 1.0)), gravity_scale: -3.0, offset: (0.0, 200.0)),
+///         (name: "ball", shape: Circle(radius: 60.0), color: LinearRgba((red: 0.0, green: 0.5, blue: 1.0, alpha: 1.0)), mass: Some(3000.0), offset: (0.0, 100.0)),
+///     ],
+///     joints: [(part1: "head", part2: "ball", kind: Rope(length: 30.0), anchor1: (0.0, -120.0), anchor2: (0.0, 20.0))],
+/// )
+/// ```
+#[derive(Asset, TypePath, Clone, Serialize, Deserialize)]
+pub struct RobotBlueprint {
+    /// Name of the part returned by [`spawn_robot`], e.g. the one which becomes the Player
+    pub root: String,
+    pub parts: Vec<PartBlueprint>,
+    #[serde(default)]
+    pub joints: Vec<JointBlueprint>,
+}
 
-    let head_bundle = robot_head(height, width);
-    let ball_bundle = robot_ball(ball_radius);
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PartBlueprint {
+    pub name: String,
+    pub shape: Shape,
+    pub color: Color,
+    /// Mass added on top of the one computed from the collider
+    #[serde(default)]
+    pub mass: Option<f32>,
+    #[serde(default = "default_gravity_scale")]
+    pub gravity_scale: f32,
+    /// Position relative to the origin passed to [`spawn_robot`]
+    #[serde(default)]
+    pub offset: Vec2,
+}
 
-    let joint = RopeJointBuilder::new(30.0)
-        .local_anchor1(Vect::new(0.0, -ball_radius*2.0)) // ball local coord
-        .local_anchor2(Vect::new(0.0, height/2.0)) // head body local coord
-        ;
-    let child = commands.spawn(ball_bundle).observe(join_cube).id();
-    let parent = commands.spawn(head_bundle).observe(join_cube).id();
-    commands
-        .entity(child)
-        .insert(ImpulseJoint::new(parent, joint));
-    parent
+fn default_gravity_scale() -> f32 {
+    1.0
 }
-#[derive(Component)]
+
+/// A joint between two parts, `part1` is the rapier parent and the
+/// joint entity is spawned as child of `part2`
+#[derive(Clone, Serialize, Deserialize)]
+pub struct JointBlueprint {
+    pub part1: String,
+    pub part2: String,
+    pub kind: JointKind,
+    /// part1 local coord
+    #[serde(default)]
+    pub anchor1: Vec2,
+    /// part2 local coord
+    #[serde(default)]
+    pub anchor2: Vec2,
+    #[serde(default)]
+    pub max_force: Option<f32>,
+}
+
+impl RobotBlueprint {
+    pub fn part(&self, name: &str) -> Option<&PartBlueprint> {
+        self.parts.iter().find(|part| part.name == name)
+    }
+
+    /// Check that the root and every joint refer to an existing part
+    pub fn validate(&self) -> Result<(), RobotBlueprintError> {
+        if self.part(&self.root).is_none() {
+            return Err(RobotBlueprintError::UnknownPart(self.root.clone()));
+        }
+        for joint in &self.joints {
+            for name in [&joint.part1, &joint.part2] {
+                if self.part(name).is_none() {
+                    return Err(RobotBlueprintError::UnknownPart(name.clone()));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The robot used before blueprints existed, kept as fallback when the
+/// asset cannot be loaded
+impl Default for RobotBlueprint {
+    fn default() -> Self {
+        let (height, width) = (40.0, 30.0);
+        let ball_radius = 60.0;
+        Self {
+            root: "head".to_string(),
+            parts: vec![
+                PartBlueprint {
+                    name: "head".to_string(),
+                    shape: Shape::Rectangle { width, height },
+                    color: Color::linear_rgb(0.1, 1.0, 0.3),
+                    mass: None,
+                    gravity_scale: -3.0,
+                    offset: Vec2::new(0.0, 200.0),
+                },
+                PartBlueprint {
+                    name: "ball".to_string(),
+                    shape: Shape::Circle {
+                        radius: ball_radius,
+                    },
+                    color: Color::linear_rgb(0.0, 0.5, 1.0),
+                    mass: Some(3000.0),
+                    gravity_scale: 1.0,
+                    offset: Vec2::new(0.0, 100.0),
+                },
+            ],
+            joints: vec![JointBlueprint {
+                part1: "head".to_string(),
+                part2: "ball".to_string(),
+                kind: JointKind::Rope { length: 30.0 },
+                anchor1: Vec2::new(0.0, -ball_radius * 2.0),
+                anchor2: Vec2::new(0.0, height / 2.0),
+                max_force: None,
+            }],
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RobotBlueprintError {
+    #[error("could not load robot blueprint: {0}")]
+    File(#[from] RonFileError),
+    #[error("robot blueprint has no part named `{0}`")]
+    UnknownPart(String),
+}
+
+#[derive(Default)]
+pub struct RobotBlueprintLoader;
+
+impl AssetLoader for RobotBlueprintLoader {
+    type Asset = RobotBlueprint;
+    type Settings = ();
+    type Error = RobotBlueprintError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let blueprint: RobotBlueprint = read_ron_asset(reader, load_context).await?;
+        blueprint.validate()?;
+        Ok(blueprint)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["robot.ron"]
+    }
+}
+
+/// Spawn every part of the blueprint around `origin` and connect them,
+/// returns the root part
+pub fn spawn_robot(commands: &mut Commands, blueprint: &RobotBlueprint, origin: Vec2) -> Entity {
+    let root = commands.spawn_empty().id();
+    let mut parts = HashMap::new();
+
+    for part in &blueprint.parts {
+        let entity = if part.name == blueprint.root {
+            root
+        } else {
+            commands.spawn_empty().id()
+        };
+        commands
+            .entity(entity)
+            .insert(robot_part(part, root, origin));
+        if let Some(mass) = part.mass {
+            commands
+                .entity(entity)
+                .insert(AdditionalMassProperties::Mass(mass));
+        }
+        parts.insert(part.name.as_str(), entity);
+    }
+
+    for joint in &blueprint.joints {
+        let (Some(&parent), Some(&child)) = (
+            parts.get(joint.part1.as_str()),
+            parts.get(joint.part2.as_str()),
+        ) else {
+            warn!(
+                "skipping robot joint {} -> {}: unknown part",
+                joint.part1, joint.part2
+            );
+            continue;
+        };
+        let spec = JointSpec::new(joint.kind, joint.anchor1, joint.anchor2)
+            .with_max_force(joint.max_force);
+        spawn_joint(commands, parent, child, spec);
+    }
+    root
+}
+
+/// Despawn every part of the robot whose root part is `robot`
+pub fn despawn_robot(commands: &mut Commands, robot: Entity, parts: &Query<(Entity, &RobotPart)>) {
+    parts
+        .iter()
+        .filter(|(_, part)| part.robot == robot)
+        .for_each(|(entity, _)| commands.entity(entity).despawn());
+}
+
+#[derive(Component, Clone, Copy, Serialize, Deserialize)]
 pub struct EntityColor(pub Color);
 
-pub fn robot_head(height: f32, width: f32) -> impl Bundle {
+fn robot_part(part: &PartBlueprint, robot: Entity, origin: Vec2) -> impl Bundle {
+    let position = origin + part.offset;
     (
-        Robot::Head,
-        Shape::Rectangle { width, height },
+        RobotPart { robot },
+        part.shape.clone(),
         RigidBody::Dynamic,
-        EntityColor(Color::linear_rgb(0.1, 1.0, 0.3)),
+        EntityColor(part.color),
         Velocity::zero(),
-        GravityScale(-3.0),
-        Transform::from_xyz(0.0, 200.0, 0.0),
-    )
-}
-pub fn robot_ball(ball_radius: f32) -> impl Bundle {
-    (
-        Robot::Ball,
-        RigidBody::Dynamic,
-        AdditionalMassProperties::Mass(3000.0),
-        EntityColor(Color::linear_rgb(0.0, 0.5, 1.0)),
-        Shape::Circle {
-            radius: ball_radius,
-        },
-        //Velocity::zero(),
-        Transform::from_xyz(0.0, 100.0, 0.0),
+        GravityScale(part.gravity_scale),
+        Transform::from_xyz(position.x, position.y, 0.0),
     )
 }
 
+/// A body belonging to the robot whose root part is `robot`
 #[derive(Component)]
-enum Robot {
-    Head,
-    Ball,
+pub struct RobotPart {
+    pub robot: Entity,
 }
@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{read_ron, write_ron},
+    cube::Cube,
+    cube_chain::{spawn_chain_pin, ChainPin},
+    input::{action_just_pressed, Action},
+    joint::{spawn_joint, Joint, JointSpec},
+    robot_constructor::{EntityColor, Shape},
+};
+
+/// Where the user-built contraption is saved and loaded
+const SAVE_PATH: &str = "saves/contraption.ron";
+
+/// Save every cube and the joints between them on SaveContraption (F5),
+/// restore them on LoadContraption (F9)
+pub struct SaveLoadPlugin;
+impl Plugin for SaveLoadPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                save_contraption.run_if(action_just_pressed(Action::SaveContraption)),
+                load_contraption.run_if(action_just_pressed(Action::LoadContraption)),
+            ),
+        );
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct SavedContraption {
+    pub cubes: Vec<SavedCube>,
+    pub joints: Vec<SavedJoint>,
+    /// Fixed bodies holding the ends of chains
+    pub pins: Vec<SavedPin>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedCube {
+    pub shape: Shape,
+    pub color: Color,
+    pub transform: Transform,
+    #[serde(default)]
+    pub linvel: Vec2,
+    #[serde(default)]
+    pub angvel: f32,
+}
+
+/// A joint between two saved cubes, `body1` and `body2` are indices into
+/// [`SavedContraption::cubes`]
+#[derive(Serialize, Deserialize)]
+pub struct SavedJoint {
+    pub body1: usize,
+    pub body2: usize,
+    pub spec: JointSpec,
+}
+
+/// A [`ChainPin`] at `position` holding the saved cube `body`, an index
+/// into [`SavedContraption::cubes`]
+#[derive(Serialize, Deserialize)]
+pub struct SavedPin {
+    pub position: Vec2,
+    pub body: usize,
+    pub spec: JointSpec,
+}
+
+impl SavedContraption {
+    /// Spawn the cubes, the joints between them and the pins, returns the cubes in
+    /// the order of [`SavedContraption::cubes`]
+    pub fn spawn(&self, commands: &mut Commands) -> Vec<Entity> {
+        let entities: Vec<Entity> = self
+            .cubes
+            .iter()
+            .map(|cube| {
+                commands
+                    .spawn(Cube::with_shape(cube.shape.clone()))
+                    .insert((
+                        EntityColor(cube.color),
+                        cube.transform,
+                        Velocity {
+                            linvel: cube.linvel,
+                            angvel: cube.angvel,
+                        },
+                    ))
+                    .id()
+            })
+            .collect();
+
+        for joint in &self.joints {
+            let (Some(&body1), Some(&body2)) =
+                (entities.get(joint.body1), entities.get(joint.body2))
+            else {
+                warn!("skipping saved joint with invalid body index");
+                continue;
+            };
+            spawn_joint(commands, body1, body2, joint.spec);
+        }
+
+        for pin in &self.pins {
+            let Some(&body) = entities.get(pin.body) else {
+                warn!("skipping saved pin with invalid body index");
+                continue;
+            };
+            spawn_chain_pin(commands, pin.position, body, pin.spec);
+        }
+        entities
+    }
+}
+
+fn save_contraption(
+    cubes: Query<(Entity, &Shape, &EntityColor, &Transform, &Velocity), With<Cube>>,
+    joints: Query<(&Joint, &JointSpec)>,
+    pins: Query<&Transform, With<ChainPin>>,
+) {
+    let mut contraption = SavedContraption::default();
+    let mut indices = HashMap::new();
+
+    for (entity, shape, color, transform, velocity) in &cubes {
+        indices.insert(entity, contraption.cubes.len());
+        contraption.cubes.push(SavedCube {
+            shape: shape.clone(),
+            color: color.0,
+            transform: *transform,
+            linvel: velocity.linvel,
+            angvel: velocity.angvel,
+        });
+    }
+
+    // joints towards anything that is neither a cube nor a pin are not saved
+    for (joint, spec) in &joints {
+        let Some(&body2) = indices.get(&joint.body2) else {
+            continue;
+        };
+        if let Some(&body1) = indices.get(&joint.body1) {
+            contraption.joints.push(SavedJoint {
+                body1,
+                body2,
+                spec: *spec,
+            });
+        } else if let Ok(pin) = pins.get(joint.body1) {
+            contraption.pins.push(SavedPin {
+                position: pin.translation.truncate(),
+                body: body2,
+                spec: *spec,
+            });
+        }
+    }
+
+    match write_ron(SAVE_PATH, &contraption) {
+        Ok(()) => info!(
+            "saved {} cubes, {} joints and {} pins to {SAVE_PATH}",
+            contraption.cubes.len(),
+            contraption.joints.len(),
+            contraption.pins.len()
+        ),
+        Err(err) => error!("{err}"),
+    }
+}
+
+/// Replace the current cubes with the saved ones, joints and pins are
+/// remapped on the freshly spawned entities, the old pins go with their cubes
+fn load_contraption(mut commands: Commands, cubes: Query<Entity, With<Cube>>) {
+    let contraption = match read_ron::<SavedContraption>(SAVE_PATH) {
+        Ok(contraption) => contraption,
+        Err(err) => {
+            error!("{err}");
+            return;
+        }
+    };
+
+    cubes
+        .iter()
+        .for_each(|entity| commands.entity(entity).despawn());
+
+    contraption.spawn(&mut commands);
+}
@@ -0,0 +1,196 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::robot_constructor::{RobotPart, Shape};
+
+/// Animates [`ShapeMorph`]: the old shape shrinks, then the new one grows
+/// only where it fits, pushing the body out of obstacles when it does not.
+/// The shapes are scaled through the Transform, which rapier applies to the
+/// collider, so each one is inserted only once
+pub struct ShapeMorphPlugin;
+impl Plugin for ShapeMorphPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ShapeMorphSettings>()
+            .add_observer(reset_morph_scale)
+            .add_systems(FixedUpdate, morph_shapes);
+    }
+}
+
+#[derive(Resource)]
+pub struct ShapeMorphSettings {
+    /// Seconds for the whole morph, half to shrink and half to grow
+    pub duration: f32,
+    /// Scale both shapes reach in the middle of the morph
+    pub min_scale: f32,
+    /// How far a growing shape can move the body to get out of an obstacle
+    pub max_push_out: f32,
+}
+
+impl Default for ShapeMorphSettings {
+    fn default() -> Self {
+        Self {
+            duration: 0.4,
+            min_scale: 0.3,
+            max_push_out: 20.0,
+        }
+    }
+}
+
+/// Insert it on an entity with a [`Shape`] to turn that shape into `to`,
+/// the component is removed when the morph is over
+#[derive(Component)]
+pub struct ShapeMorph {
+    pub from: Shape,
+    pub to: Shape,
+    elapsed: f32,
+    duration: f32,
+    /// Set when `to` did not fit and the morph is going back to `from`
+    reverted: bool,
+    /// Set once `to` has replaced `from` on the entity
+    grown: bool,
+}
+
+impl ShapeMorph {
+    pub fn new(from: Shape, to: Shape, duration: f32) -> Self {
+        Self {
+            from,
+            to,
+            elapsed: 0.0,
+            duration,
+            reverted: false,
+            grown: false,
+        }
+    }
+
+    /// Scale of the current shape at `elapsed` seconds and whether it is
+    /// `to` growing
+    fn scale_at(&self, elapsed: f32, min_scale: f32) -> (f32, bool) {
+        let t = (elapsed / self.duration).clamp(0.0, 1.0);
+        if t < 0.5 {
+            (1.0_f32.lerp(min_scale, t * 2.0), false)
+        } else {
+            (min_scale.lerp(1.0, t * 2.0 - 1.0), true)
+        }
+    }
+
+    /// The same morph played backward from the current shape
+    fn reversed(&self) -> Self {
+        Self {
+            from: self.to.clone(),
+            to: self.from.clone(),
+            elapsed: self.duration - self.elapsed,
+            duration: self.duration,
+            reverted: true,
+            // the shape on the entity stays, it is the other end of the morph now
+            grown: !self.grown,
+        }
+    }
+}
+
+/// Directions tried, in order, to push a growing shape out of an obstacle
+const PUSH_OUT_DIRECTIONS: [Vec2; 8] = [
+    Vec2::Y,
+    Vec2::new(-1.0, 1.0),
+    Vec2::new(1.0, 1.0),
+    Vec2::NEG_X,
+    Vec2::X,
+    Vec2::new(-1.0, -1.0),
+    Vec2::new(1.0, -1.0),
+    Vec2::NEG_Y,
+];
+const PUSH_OUT_STEP: f32 = 2.0;
+
+fn morph_shapes(
+    mut commands: Commands,
+    mut morphing: Query<(Entity, &mut ShapeMorph, &mut Transform, Option<&RobotPart>)>,
+    parts: Query<&RobotPart>,
+    rapier_context: ReadRapierContext,
+    settings: Res<ShapeMorphSettings>,
+    time: Res<Time>,
+) {
+    let Ok(context) = rapier_context.single() else {
+        return;
+    };
+
+    for (entity, mut morph, mut transform, robot_part) in &mut morphing {
+        let elapsed = morph.elapsed + time.delta_secs();
+        let (scale, growing) = morph.scale_at(elapsed, settings.min_scale);
+
+        if growing {
+            // the other parts of the same robot are joined to it, ignore them
+            let robot = robot_part.map(|part| part.robot);
+            let other_robot = |other: Entity| {
+                robot.is_none() || parts.get(other).ok().map(|part| part.robot) != robot
+            };
+            let filter = QueryFilter::default()
+                .exclude_sensors()
+                .exclude_rigid_body(entity)
+                .predicate(&other_robot);
+            let collider = morph.to.scaled(scale).collider();
+            let rotation = transform.rotation.to_euler(EulerRot::ZYX).0;
+            let fits = |position: Vec2| {
+                context
+                    .cast_shape(
+                        position,
+                        rotation,
+                        Vec2::ZERO,
+                        &collider,
+                        ShapeCastOptions::with_max_time_of_impact(0.0),
+                        filter,
+                    )
+                    .is_none()
+            };
+
+            let position = transform.translation.truncate();
+            let free_position = if fits(position) {
+                Some(position)
+            } else {
+                push_out(position, settings.max_push_out, &fits)
+            };
+            match free_position {
+                Some(free_position) if free_position != position => {
+                    transform.translation = free_position.extend(transform.translation.z);
+                }
+                Some(_) => {}
+                None if !morph.reverted => {
+                    *morph = morph.reversed();
+                    continue;
+                }
+                // not even the original shape fits anymore, wait for room
+                None => continue,
+            }
+        }
+
+        morph.elapsed = elapsed;
+        if growing && !morph.grown {
+            morph.grown = true;
+            commands.entity(entity).insert(morph.to.clone());
+        }
+        if morph.elapsed >= morph.duration {
+            commands.entity(entity).remove::<ShapeMorph>();
+        } else {
+            transform.scale = Vec3::new(scale, scale, 1.0);
+        }
+    }
+}
+
+/// Back to full size when a morph ends or is replaced by another one
+fn reset_morph_scale(
+    trigger: Trigger<OnReplace, ShapeMorph>,
+    mut transforms: Query<&mut Transform>,
+) {
+    if let Ok(mut transform) = transforms.get_mut(trigger.target()) {
+        transform.scale = Vec3::ONE;
+    }
+}
+
+/// Closest free position around `position`, at most `max_distance` away
+fn push_out(position: Vec2, max_distance: f32, fits: impl Fn(Vec2) -> bool) -> Option<Vec2> {
+    let steps = (max_distance / PUSH_OUT_STEP) as u32;
+    (1..=steps)
+        .flat_map(|step| {
+            let distance = PUSH_OUT_STEP * step as f32;
+            PUSH_OUT_DIRECTIONS.map(|direction| position + direction.normalize() * distance)
+        })
+        .find(|candidate| fits(*candidate))
+}
@@ -0,0 +1,134 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::cube::Cube;
+
+use super::{
+    terrain_plugin::{Crater, Terrain, TerrainChunks, TerrainEdits, TerrainSettings},
+    tile_editor_plugin::TerrainTile,
+};
+
+/// Digs craters in the terrain on [`DigTerrain`], sent when a cube hits the
+/// terrain hard enough or by the player dig skill
+pub struct TerrainDestructionPlugin;
+impl Plugin for TerrainDestructionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TerrainDestruction>()
+            .add_event::<DigTerrain>()
+            .add_observer(listen_to_cube_impacts)
+            .add_systems(Update, (cube_impacts, dig_terrain).chain());
+    }
+}
+
+#[derive(Resource)]
+pub struct TerrainDestruction {
+    /// Contact force over which a cube digs the terrain it hits, in rapier
+    /// units: a falling 30px cube weighs about 0.1 and hits with ~50
+    pub impact_threshold: f32,
+    /// Crater radius relative to the size of the cube
+    pub impact_crater_scale: f32,
+    pub dig_radius: f32,
+    /// How far from the player the dig skill reaches
+    pub dig_reach: f32,
+}
+
+impl Default for TerrainDestruction {
+    fn default() -> Self {
+        Self {
+            impact_threshold: 40.0,
+            impact_crater_scale: 0.75,
+            dig_radius: 40.0,
+            dig_reach: 300.0,
+        }
+    }
+}
+
+/// Remove a disk of terrain and rebuild the chunks it overlaps
+#[derive(Event, Clone, Copy, Debug)]
+pub struct DigTerrain(pub Crater);
+
+/// Cubes report when they touch something and the contact forces going
+/// over the impact threshold
+fn listen_to_cube_impacts(
+    trigger: Trigger<OnAdd, Cube>,
+    destruction: Res<TerrainDestruction>,
+    mut commands: Commands,
+) {
+    commands.entity(trigger.target()).insert((
+        ActiveEvents::COLLISION_EVENTS | ActiveEvents::CONTACT_FORCE_EVENTS,
+        ContactForceEventThreshold(destruction.impact_threshold),
+    ));
+}
+
+/// Only the contact that just started digs, a cube resting on or sliding
+/// along the ground keeps reporting forces every step. A cube landing in a
+/// crater does not dig it again, rebuilding the chunk under a resting cube
+/// starts a new contact
+fn cube_impacts(
+    mut collisions: EventReader<CollisionEvent>,
+    mut contact_forces: EventReader<ContactForceEvent>,
+    cubes: Query<(&Cube, &GlobalTransform)>,
+    // craters are dug in the generated ground, painted tiles stay whole
+    terrain: Query<(), (With<Terrain>, Without<TerrainTile>)>,
+    destruction: Res<TerrainDestruction>,
+    edits: Res<TerrainEdits>,
+    mut dig: EventWriter<DigTerrain>,
+) {
+    let cube_on_terrain = |entity1: Entity, entity2: Entity| match (entity1, entity2) {
+        (cube, other) | (other, cube) if terrain.contains(other) && cubes.contains(cube) => {
+            Some(cube)
+        }
+        _ => None,
+    };
+    let landed: HashSet<Entity> = collisions
+        .read()
+        .filter_map(|collision| match *collision {
+            CollisionEvent::Started(entity1, entity2, _) => cube_on_terrain(entity1, entity2),
+            CollisionEvent::Stopped(..) => None,
+        })
+        .collect();
+
+    for event in contact_forces.read() {
+        if event.total_force_magnitude < destruction.impact_threshold {
+            continue;
+        }
+        let Some(cube) =
+            cube_on_terrain(event.collider1, event.collider2).filter(|cube| landed.contains(cube))
+        else {
+            continue;
+        };
+        let Ok((cube, transform)) = cubes.get(cube) else {
+            continue;
+        };
+        let center = transform.translation().truncate();
+        if edits
+            .craters
+            .iter()
+            .any(|crater| crater.center.distance(center) < crater.radius)
+        {
+            continue;
+        }
+        dig.write(DigTerrain(Crater {
+            center,
+            radius: cube.width.max(cube.height) * destruction.impact_crater_scale,
+        }));
+    }
+}
+
+fn dig_terrain(
+    mut events: EventReader<DigTerrain>,
+    mut edits: ResMut<TerrainEdits>,
+    mut chunks: ResMut<TerrainChunks>,
+    settings: Res<TerrainSettings>,
+) {
+    for DigTerrain(crater) in events.read() {
+        edits.craters.push(*crater);
+        chunks.mark_dirty(
+            crater.center.x - crater.radius,
+            crater.center.x + crater.radius,
+            settings.chunk_width(),
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The ground profile, every generator is a pure function of `x` and the
+/// seed so that any part of the terrain can be generated on its own
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum TerrainGenerator {
+    Flat {
+        height: f32,
+    },
+    /// Two slopes meeting at `center`, the old V shaped terrain
+    Valley {
+        center: f32,
+        height: f32,
+        slope: f32,
+    },
+    /// Value noise summed over `octaves`, each one twice the frequency and
+    /// half the amplitude of the previous
+    Hills {
+        height: f32,
+        amplitude: f32,
+        wavelength: f32,
+        octaves: u32,
+    },
+    /// Steps going up toward positive x, every step height varies a bit with the seed
+    Stairs {
+        height: f32,
+        step_width: f32,
+        step_height: f32,
+    },
+    /// Hilly ground under a noisy ceiling, `clearance` is the mean tunnel height
+    Caves {
+        height: f32,
+        amplitude: f32,
+        wavelength: f32,
+        clearance: f32,
+    },
+}
+
+impl TerrainGenerator {
+    /// Height of the ground at `x`
+    pub fn height(&self, x: f32, seed: u32) -> f32 {
+        match *self {
+            TerrainGenerator::Flat { height } => height,
+            TerrainGenerator::Valley {
+                center,
+                height,
+                slope,
+            } => height + (x - center).abs() * slope,
+            TerrainGenerator::Hills {
+                height,
+                amplitude,
+                wavelength,
+                octaves,
+            } => height + amplitude * fractal_noise(x / wavelength, seed, octaves),
+            TerrainGenerator::Stairs {
+                height,
+                step_width,
+                step_height,
+            } => {
+                let step = (x / step_width).floor();
+                let jitter = 0.25 * lattice_value(step as i32, seed);
+                height + (step + jitter) * step_height
+            }
+            TerrainGenerator::Caves {
+                height,
+                amplitude,
+                wavelength,
+                ..
+            } => height + amplitude * fractal_noise(x / wavelength, seed, 3),
+        }
+    }
+
+    /// Height of the ceiling at `x`, only caves have one
+    pub fn ceiling(&self, x: f32, seed: u32) -> Option<f32> {
+        match *self {
+            TerrainGenerator::Caves {
+                amplitude,
+                wavelength,
+                clearance,
+                ..
+            } => {
+                // a different seed so that the ceiling does not follow the ground
+                let offset = amplitude * fractal_noise(x / wavelength, seed.wrapping_add(1), 3);
+                // never lower than a third of the clearance above the ground
+                let ceiling = self.height(x, seed) + clearance + offset;
+                Some(ceiling.max(self.height(x, seed) + clearance / 3.0))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Pseudo random value in [-1, 1] for an integer lattice point
+fn lattice_value(i: i32, seed: u32) -> f32 {
+    let mut hash = (i as u32).wrapping_mul(0x9E37_79B1) ^ seed.wrapping_mul(0x85EB_CA77);
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(0x2C1B_3C6D);
+    hash ^= hash >> 12;
+    hash = hash.wrapping_mul(0x297A_2D39);
+    hash ^= hash >> 15;
+    hash as f32 / u32::MAX as f32 * 2.0 - 1.0
+}
+
+/// Smooth 1D value noise in [-1, 1], one random value every unit of `x`
+pub fn value_noise(x: f32, seed: u32) -> f32 {
+    let i = x.floor();
+    let t = x - i;
+    let t = t * t * (3.0 - 2.0 * t);
+    let i = i as i32;
+    lattice_value(i, seed).lerp(lattice_value(i.wrapping_add(1), seed), t)
+}
+
+/// [`value_noise`] octaves normalized back to [-1, 1]
+pub fn fractal_noise(x: f32, seed: u32, octaves: u32) -> f32 {
+    let (mut sum, mut amplitude, mut total, mut frequency) = (0.0, 1.0, 0.0, 1.0);
+    for octave in 0..octaves.max(1) {
+        sum += amplitude * value_noise(x * frequency, seed.wrapping_add(octave * 7919));
+        total += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+    sum / total
+}
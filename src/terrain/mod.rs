@@ -0,0 +1,4 @@
+pub mod destruction_plugin;
+pub mod generators;
+pub mod terrain_plugin;
+pub mod tile_editor_plugin;
@@ -0,0 +1,350 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::{
+    asset::RenderAssetUsages,
+    color::palettes::tailwind::GREEN_800,
+    prelude::*,
+    render::mesh::{Indices, PrimitiveTopology},
+};
+use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    destruction_plugin::TerrainDestructionPlugin, generators::TerrainGenerator,
+    tile_editor_plugin::TileEditorPlugin,
+};
+
+/// Streams the ground described by [`TerrainSettings`] around the camera,
+/// one chunk of polyline colliders with a matching filled mesh at a time
+pub struct TerrainPlugin;
+impl Plugin for TerrainPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((TerrainDestructionPlugin, TileEditorPlugin))
+            .init_resource::<TerrainSettings>()
+            .init_resource::<TerrainChunks>()
+            .init_resource::<TerrainEdits>()
+            .add_systems(Update, stream_terrain_chunks);
+    }
+}
+
+/// Chunks kept on each side of the one under the camera at most
+pub const MAX_VIEW_DISTANCE: u32 = 16;
+
+#[derive(Component)]
+pub struct Terrain;
+
+/// Marks the terrain entities spawned by stream_terrain_chunks, chunk `i`
+/// spans from `i * chunk_width` to `(i + 1) * chunk_width`
+#[derive(Component)]
+pub struct TerrainChunk;
+
+/// Which side of a terrain surface is solid
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TerrainSide {
+    /// The ground, filled down to `depth` below its lowest point
+    Below,
+    /// A cave ceiling, filled up to `depth` above its highest point
+    Above,
+}
+
+/// Missing fields of a level file take their default value
+#[derive(Resource, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TerrainSettings {
+    pub generator: TerrainGenerator,
+    pub seed: u32,
+    /// Read through [`TerrainSettings::chunk_width`], which keeps it at
+    /// least `resolution` wide
+    pub chunk_width: f32,
+    /// Read through [`TerrainSettings::view_distance`], which caps it at
+    /// [`MAX_VIEW_DISTANCE`]
+    pub view_distance: u32,
+    /// Horizontal distance between two points of the surface
+    pub resolution: f32,
+    /// Thickness of the mesh under the ground and over the ceiling
+    pub depth: f32,
+    pub color: Color,
+}
+
+impl Default for TerrainSettings {
+    fn default() -> Self {
+        Self {
+            generator: TerrainGenerator::Valley {
+                center: -200.0,
+                height: -100.0,
+                slope: 0.3,
+            },
+            seed: 0,
+            chunk_width: 1000.0,
+            view_distance: 2,
+            resolution: 20.0,
+            depth: 200.0,
+            color: GREEN_800.into(),
+        }
+    }
+}
+
+impl TerrainSettings {
+    /// `chunk_width` clamped, level files may set it to zero or below
+    pub fn chunk_width(&self) -> f32 {
+        self.chunk_width.max(self.resolution).max(1.0)
+    }
+
+    /// `view_distance` clamped, level files may ask for any number of chunks
+    pub fn view_distance(&self) -> i32 {
+        self.view_distance.min(MAX_VIEW_DISTANCE) as i32
+    }
+
+    /// Surface points between `start` and `end`, both included, with the
+    /// craters dug so far
+    pub fn surface(
+        &self,
+        start: f32,
+        end: f32,
+        side: TerrainSide,
+        edits: &TerrainEdits,
+    ) -> Option<Vec<Vec2>> {
+        let resolution = self.resolution.max(1.0);
+        let count = ((end - start) / resolution).ceil().max(1.0) as usize;
+        let craters: Vec<&Crater> = edits
+            .craters
+            .iter()
+            .filter(|crater| {
+                crater.center.x + crater.radius >= start && crater.center.x - crater.radius <= end
+            })
+            .collect();
+        (0..=count)
+            .map(|i| {
+                let x = (start + i as f32 * resolution).min(end);
+                let y = match side {
+                    TerrainSide::Below => Some(self.generator.height(x, self.seed)),
+                    TerrainSide::Above => self.generator.ceiling(x, self.seed),
+                }?;
+                let y = craters.iter().fold(y, |y, crater| crater.carve(x, y, side));
+                Some(Vec2::new(x, y))
+            })
+            .collect()
+    }
+}
+
+/// A disk removed from the terrain
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Crater {
+    pub center: Vec2,
+    pub radius: f32,
+}
+
+impl Crater {
+    /// The surface height at `x` once the crater is dug, the terrain is a
+    /// height profile so everything on the open side of the disk goes too
+    fn carve(&self, x: f32, y: f32, side: TerrainSide) -> f32 {
+        let dx = x - self.center.x;
+        if dx.abs() >= self.radius {
+            return y;
+        }
+        let half_chord = (self.radius * self.radius - dx * dx).sqrt();
+        match side {
+            TerrainSide::Below if self.center.y - half_chord < y => {
+                y.min(self.center.y - half_chord)
+            }
+            TerrainSide::Above if self.center.y + half_chord > y => {
+                y.max(self.center.y + half_chord)
+            }
+            _ => y,
+        }
+    }
+}
+
+/// Every crater dug in the terrain, applied again whenever a chunk is
+/// generated so that revisited chunks keep them
+#[derive(Resource, Default)]
+pub struct TerrainEdits {
+    pub craters: Vec<Crater>,
+}
+
+/// Spawn the terrain between `start` and `end`: the ground and, if the
+/// generator has one, the ceiling
+pub fn spawn_terrain_section(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    material: Handle<ColorMaterial>,
+    settings: &TerrainSettings,
+    edits: &TerrainEdits,
+    start: f32,
+    end: f32,
+) -> Vec<Entity> {
+    [TerrainSide::Below, TerrainSide::Above]
+        .into_iter()
+        .filter_map(|side| {
+            let points = settings.surface(start, end, side, edits)?;
+            Some(
+                commands
+                    .spawn((
+                        Terrain,
+                        RigidBody::Fixed,
+                        Collider::polyline(points.clone(), None),
+                        Mesh2d(meshes.add(terrain_mesh(&points, side, settings.depth))),
+                        MeshMaterial2d(material.clone()),
+                        Transform::default(),
+                    ))
+                    .id(),
+            )
+        })
+        .collect()
+}
+
+/// The loaded chunks and the material they share
+#[derive(Resource)]
+pub struct TerrainChunks {
+    pub loaded: HashMap<i32, Vec<Entity>>,
+    /// Chunks to rebuild since the terrain changed
+    pub dirty: HashSet<i32>,
+    pub material: Handle<ColorMaterial>,
+}
+
+impl TerrainChunks {
+    /// Mark the chunks overlapping `start..=end` for rebuild
+    pub fn mark_dirty(&mut self, start: f32, end: f32, chunk_width: f32) {
+        let chunk_width = chunk_width.max(1.0);
+        let first = (start / chunk_width).floor() as i32;
+        let last = (end / chunk_width).floor() as i32;
+        self.dirty.extend(first..=last);
+    }
+}
+
+impl FromWorld for TerrainChunks {
+    fn from_world(world: &mut World) -> Self {
+        let color = world.resource::<TerrainSettings>().color;
+        Self {
+            loaded: HashMap::new(),
+            dirty: HashSet::new(),
+            material: world
+                .resource_mut::<Assets<ColorMaterial>>()
+                .add(ColorMaterial::from_color(color)),
+        }
+    }
+}
+
+/// Spawn the chunks within `view_distance` of the camera and despawn the
+/// others, generators only depend on x and the seed so a chunk spawned again
+/// is identical. Chunks under a dynamic body stay loaded, out of view or not,
+/// so that it does not fall through the ground. Every chunk is rebuilt and
+/// the craters are forgotten when the settings change
+#[allow(clippy::too_many_arguments)]
+fn stream_terrain_chunks(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut chunks: ResMut<TerrainChunks>,
+    mut edits: ResMut<TerrainEdits>,
+    settings: Res<TerrainSettings>,
+    camera: Single<&GlobalTransform, With<Camera2d>>,
+    bodies: Query<(&RigidBody, &GlobalTransform)>,
+) {
+    if settings.is_changed() {
+        edits.craters.clear();
+        chunks.dirty.clear();
+        for (_, entities) in chunks.loaded.drain() {
+            entities
+                .into_iter()
+                .for_each(|entity| commands.entity(entity).despawn());
+        }
+        if let Some(material) = materials.get_mut(&chunks.material) {
+            material.color = settings.color;
+        }
+    }
+
+    let chunk_width = settings.chunk_width();
+    let center = (camera.translation().x / chunk_width).floor() as i32;
+    let distance = settings.view_distance();
+    let mut wanted: HashSet<i32> =
+        (center.saturating_sub(distance)..=center.saturating_add(distance)).collect();
+    // the neighbours too, a body can rest across the edge of its chunk
+    for (body, transform) in &bodies {
+        if *body == RigidBody::Dynamic {
+            let index = (transform.translation().x / chunk_width).floor() as i32;
+            wanted.extend(index.saturating_sub(1)..=index.saturating_add(1));
+        }
+    }
+
+    let dirty = std::mem::take(&mut chunks.dirty);
+    chunks.loaded.retain(|index, entities| {
+        let keep = wanted.contains(index) && !dirty.contains(index);
+        if !keep {
+            entities
+                .iter()
+                .for_each(|entity| commands.entity(*entity).despawn());
+        }
+        keep
+    });
+
+    for index in wanted {
+        if chunks.loaded.contains_key(&index) {
+            continue;
+        }
+        let start = index as f32 * chunk_width;
+        let entities = spawn_terrain_section(
+            &mut commands,
+            &mut meshes,
+            chunks.material.clone(),
+            &settings,
+            &edits,
+            start,
+            start + chunk_width,
+        );
+        for entity in &entities {
+            commands.entity(*entity).insert(TerrainChunk);
+        }
+        chunks.loaded.insert(index, entities);
+    }
+}
+
+/// A strip of quads between the surface and a flat edge `depth` past its
+/// farthest point on the solid side
+pub fn terrain_mesh(points: &[Vec2], side: TerrainSide, depth: f32) -> Mesh {
+    let edge_y = match side {
+        TerrainSide::Below => points.iter().map(|p| p.y).fold(f32::INFINITY, f32::min) - depth,
+        TerrainSide::Above => points.iter().map(|p| p.y).fold(f32::NEG_INFINITY, f32::max) + depth,
+    };
+    let (min_x, max_x) = points
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), p| {
+            (min.min(p.x), max.max(p.x))
+        });
+    let span = (max_x - min_x).max(f32::EPSILON);
+
+    let mut positions = Vec::with_capacity(points.len() * 2);
+    let mut uvs = Vec::with_capacity(points.len() * 2);
+    for point in points {
+        let u = (point.x - min_x) / span;
+        positions.push([point.x, point.y, 0.0]);
+        positions.push([point.x, edge_y, 0.0]);
+        uvs.push([u, 0.0]);
+        uvs.push([u, 1.0]);
+    }
+    let mut indices = Vec::with_capacity(points.len().saturating_sub(1) * 6);
+    for i in 0..points.len().saturating_sub(1) as u32 {
+        let (surface, edge, next_surface, next_edge) = (i * 2, i * 2 + 1, i * 2 + 2, i * 2 + 3);
+        // counter clockwise whichever side the edge is on
+        match side {
+            TerrainSide::Below => {
+                indices.extend([surface, edge, next_edge, surface, next_edge, next_surface])
+            }
+            TerrainSide::Above => {
+                indices.extend([surface, next_edge, edge, surface, next_surface, next_edge])
+            }
+        }
+    }
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(
+        Mesh::ATTRIBUTE_NORMAL,
+        vec![[0.0, 0.0, 1.0]; positions.len()],
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+    .with_inserted_indices(Indices::U32(indices))
+}
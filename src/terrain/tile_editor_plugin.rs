@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::write_ron,
+    input::{action_just_pressed, Action},
+    player::{
+        player_plugin::{ClickMode, Player},
+        resources::PlayerMouseCoor,
+    },
+    robot_constructor::{EntityColor, Shape},
+};
+
+use super::terrain_plugin::Terrain;
+
+/// Where ExportTiles (F6) writes the painted tiles, next to the level files
+/// whose `tiles` field it fits
+const PAINTED_LEVEL_PATH: &str = "assets/levels/painted.tiles.ron";
+
+/// ClickMode::PaintTerrain: left drag paints fixed terrain tiles on a grid
+/// with the selected [`TileMaterial`], right drag erases them
+pub struct TileEditorPlugin;
+impl Plugin for TileEditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TileEditor>()
+            .init_resource::<PaintedTiles>()
+            .add_systems(
+                Update,
+                (
+                    paint_tiles,
+                    export_painted_level.run_if(action_just_pressed(Action::ExportTiles)),
+                ),
+            );
+    }
+}
+
+/// What a tile is made of, sets its rapier `Friction` and `Restitution`
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum TileMaterial {
+    #[default]
+    Normal,
+    Ice,
+    Rubber,
+}
+
+impl TileMaterial {
+    pub fn next(self) -> Self {
+        match self {
+            TileMaterial::Normal => TileMaterial::Ice,
+            TileMaterial::Ice => TileMaterial::Rubber,
+            TileMaterial::Rubber => TileMaterial::Normal,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TileMaterial::Normal => "Normal",
+            TileMaterial::Ice => "Ice",
+            TileMaterial::Rubber => "Rubber",
+        }
+    }
+
+    pub fn friction(self) -> f32 {
+        match self {
+            TileMaterial::Normal => 0.5,
+            TileMaterial::Ice => 0.02,
+            TileMaterial::Rubber => 1.0,
+        }
+    }
+
+    pub fn restitution(self) -> f32 {
+        match self {
+            TileMaterial::Normal => 0.0,
+            TileMaterial::Ice => 0.05,
+            TileMaterial::Rubber => 0.9,
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            TileMaterial::Normal => Color::srgb(0.45, 0.32, 0.2),
+            TileMaterial::Ice => Color::srgb(0.7, 0.9, 1.0),
+            TileMaterial::Rubber => Color::srgb(0.9, 0.3, 0.5),
+        }
+    }
+}
+
+/// Grid and material used to paint tiles
+#[derive(Resource)]
+pub struct TileEditor {
+    /// Side of a tile, the grid starts at the world origin
+    pub tile_size: f32,
+    pub material: TileMaterial,
+}
+
+impl Default for TileEditor {
+    fn default() -> Self {
+        Self {
+            tile_size: 40.0,
+            material: TileMaterial::Normal,
+        }
+    }
+}
+
+impl TileEditor {
+    /// Grid cell containing a world position
+    pub fn cell(&self, position: Vec2) -> IVec2 {
+        (position / self.tile_size).floor().as_ivec2()
+    }
+}
+
+/// A painted tile and the grid cell it fills
+#[derive(Component)]
+pub struct TerrainTile {
+    pub cell: IVec2,
+    pub material: TileMaterial,
+}
+
+/// Every painted tile by grid cell
+#[derive(Resource, Default)]
+pub struct PaintedTiles(pub HashMap<IVec2, Entity>);
+
+/// The painted tiles as written to a level file
+///
+/// ```ron
+/// (
+///     tile_size: 40.0,
+///     tiles: [
+///         (cell: (0, -3), material: Normal),
+///         (cell: (1, -3), material: Ice),
+///     ],
+/// )
+/// ```
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct PaintedLevel {
+    pub tile_size: f32,
+    pub tiles: Vec<PaintedTile>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct PaintedTile {
+    pub cell: IVec2,
+    pub material: TileMaterial,
+}
+
+impl Default for PaintedLevel {
+    fn default() -> Self {
+        Self {
+            tile_size: TileEditor::default().tile_size,
+            tiles: Vec::new(),
+        }
+    }
+}
+
+/// Spawn a fixed tile filling `cell` of a grid of `tile_size`
+pub fn spawn_tile(
+    commands: &mut Commands,
+    tile_size: f32,
+    cell: IVec2,
+    material: TileMaterial,
+) -> Entity {
+    let half = tile_size / 2.0;
+    let center = (cell.as_vec2() + 0.5) * tile_size;
+    commands
+        .spawn((
+            Terrain,
+            TerrainTile { cell, material },
+            RigidBody::Fixed,
+            Shape::Rectangle {
+                width: half,
+                height: half,
+            },
+            EntityColor(material.color()),
+            Friction::coefficient(material.friction()),
+            Restitution::coefficient(material.restitution()),
+            Transform::from_translation(center.extend(0.0)),
+        ))
+        .id()
+}
+
+fn paint_tiles(
+    mut commands: Commands,
+    mut painted: ResMut<PaintedTiles>,
+    tiles: Query<&TerrainTile>,
+    editor: Res<TileEditor>,
+    mouse_coor: Res<PlayerMouseCoor>,
+    actions: Res<ButtonInput<Action>>,
+    player: Single<&Player>,
+) {
+    if player.0 != ClickMode::PaintTerrain {
+        return;
+    }
+    let cell = editor.cell(mouse_coor.into_inner().into());
+    if actions.pressed(Action::SpawnCube) {
+        // painting over a tile of another material replaces it
+        if let Some(&entity) = painted.0.get(&cell) {
+            if tiles
+                .get(entity)
+                .is_ok_and(|tile| tile.material == editor.material)
+            {
+                return;
+            }
+            commands.entity(entity).despawn();
+        }
+        let entity = spawn_tile(&mut commands, editor.tile_size, cell, editor.material);
+        painted.0.insert(cell, entity);
+    } else if actions.pressed(Action::DeleteCube) {
+        if let Some(entity) = painted.0.remove(&cell) {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn export_painted_level(tiles: Query<&TerrainTile>, editor: Res<TileEditor>) {
+    let mut level = PaintedLevel {
+        tile_size: editor.tile_size,
+        tiles: tiles
+            .iter()
+            .map(|tile| PaintedTile {
+                cell: tile.cell,
+                material: tile.material,
+            })
+            .collect(),
+    };
+    // stable output whatever the query order
+    level.tiles.sort_by_key(|tile| (tile.cell.y, tile.cell.x));
+    match write_ron(PAINTED_LEVEL_PATH, &level) {
+        Ok(()) => info!(
+            "exported {} tiles to {PAINTED_LEVEL_PATH}",
+            level.tiles.len()
+        ),
+        Err(err) => error!("{err}"),
+    }
+}
@@ -0,0 +1,25 @@
+use bevy::prelude::*;
+
+/// Button switching the player ClickMode
+#[derive(Component)]
+pub struct ClickModeButton;
+
+/// Button cycling the joint type used by JoinCube
+#[derive(Component)]
+pub struct JointKindButton;
+
+/// Button cycling the shape spawned by SpawnCube
+#[derive(Component)]
+pub struct ShapePaletteButton;
+
+/// Button cycling the player forms, its text follows PlayerFormChanged
+#[derive(Component)]
+pub struct PlayerFormButton;
+
+/// Button cycling the material painted in PaintTerrain
+#[derive(Component)]
+pub struct TileMaterialButton;
+
+/// Text shown for a few seconds when the player reaches a goal
+#[derive(Component)]
+pub struct GoalMessage(pub Timer);
@@ -1,6 +1,7 @@
 use bevy::color::Color;
 
 pub mod bundles;
+pub mod components;
 pub mod events;
 pub mod resources;
 pub mod systems;
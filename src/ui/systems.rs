@@ -1,37 +1,28 @@
 use bevy::{color::palettes::basic::*, prelude::*};
 
-use crate::player::player_plugin::{ClickMode, Player};
+use crate::{
+    joint::JointSelection,
+    level::GoalReached,
+    player::{
+        player_forms_plugin::{ChangeShape, PlayerFormChanged},
+        player_plugin::Player,
+    },
+    shape_morph::ShapeMorph,
+    terrain::tile_editor_plugin::TileEditor,
+};
 
-use super::*;
+use super::{components::*, *};
 
+/// Hover and press feedback shared by every button
 pub fn button_system(
     mut interaction_query: Query<
-        (
-            &Interaction,
-            &mut BackgroundColor,
-            &mut BorderColor,
-            &Children,
-        ),
+        (&Interaction, &mut BackgroundColor, &mut BorderColor),
         (Changed<Interaction>, With<Button>),
     >,
-    mut text_query: Query<&mut Text>,
-    mut player: Single<&mut Player>,
 ) {
-    for (interaction, mut color, mut border_color, children) in &mut interaction_query {
-        let mut text = text_query.get_mut(children[0]).unwrap();
+    for (interaction, mut color, mut border_color) in &mut interaction_query {
         match *interaction {
             Interaction::Pressed => {
-                **text = match player.0 {
-                    crate::player::player_plugin::ClickMode::SpawnCube => {
-                        player.0 = ClickMode::JoinCube;
-                        "JoinCube".to_string()
-                    }
-                    crate::player::player_plugin::ClickMode::JoinCube => {
-                        player.0 = ClickMode::SpawnCube;
-                        "SpawnCube".to_string()
-                    }
-                };
-
                 *color = PRESSED_BUTTON.into();
                 border_color.0 = RED.into();
             }
@@ -46,3 +37,113 @@ pub fn button_system(
         }
     }
 }
+
+pub fn click_mode_button(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<ClickModeButton>)>,
+    mut player: Single<&mut Player>,
+) {
+    for interaction in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            player.0 = player.0.next();
+        }
+    }
+}
+
+/// The ClickMode also changes with the ToggleClickMode action
+pub fn update_click_mode_text(
+    player: Single<&Player, Changed<Player>>,
+    button: Single<&Children, With<ClickModeButton>>,
+    mut text_query: Query<&mut Text>,
+) {
+    let mut text = text_query.get_mut(button[0]).unwrap();
+    **text = player.0.name().to_string();
+}
+
+pub fn joint_kind_button(
+    interaction_query: Query<
+        (&Interaction, &Children),
+        (Changed<Interaction>, With<JointKindButton>),
+    >,
+    mut text_query: Query<&mut Text>,
+    mut joint_selection: ResMut<JointSelection>,
+) {
+    for (interaction, children) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let mut text = text_query.get_mut(children[0]).unwrap();
+        **text = format!("Joint: {}", joint_selection.next().name());
+    }
+}
+
+pub fn shape_palette_button(
+    interaction_query: Query<
+        (&Interaction, &Children),
+        (Changed<Interaction>, With<ShapePaletteButton>),
+    >,
+    mut text_query: Query<&mut Text>,
+    mut palette: ResMut<ShapePalette>,
+) {
+    for (interaction, children) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let mut text = text_query.get_mut(children[0]).unwrap();
+        **text = format!("Shape: {}", palette.next());
+    }
+}
+
+pub fn tile_material_button(
+    interaction_query: Query<
+        (&Interaction, &Children),
+        (Changed<Interaction>, With<TileMaterialButton>),
+    >,
+    mut text_query: Query<&mut Text>,
+    mut editor: ResMut<TileEditor>,
+) {
+    for (interaction, children) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        editor.material = editor.material.next();
+        let mut text = text_query.get_mut(children[0]).unwrap();
+        **text = format!("Tile: {}", editor.material.name());
+    }
+}
+
+/// Like the ChangeShape action, ignored while the player is morphing
+pub fn player_form_button(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<PlayerFormButton>)>,
+    player: Single<Entity, (With<Player>, Without<ShapeMorph>)>,
+    mut commands: Commands,
+) {
+    for interaction in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            commands.trigger_targets(ChangeShape { step: 1 }, *player);
+        }
+    }
+}
+
+pub fn on_player_form_changed(
+    trigger: Trigger<PlayerFormChanged>,
+    button: Single<&Children, With<PlayerFormButton>>,
+    mut text_query: Query<&mut Text>,
+) {
+    let mut text = text_query.get_mut(button[0]).unwrap();
+    **text = format!("Form {}: {}", trigger.index + 1, trigger.name);
+}
+
+/// Show the goal message on GoalReached, hide it once its timer is over
+pub fn show_goal_message(
+    mut goal_reached: EventReader<GoalReached>,
+    message: Single<(&mut GoalMessage, &mut Visibility)>,
+    time: Res<Time>,
+) {
+    let (mut message, mut visibility) = message.into_inner();
+    if goal_reached.read().count() > 0 {
+        message.0.reset();
+        *visibility = Visibility::Visible;
+    } else if message.0.tick(time.delta()).just_finished() {
+        *visibility = Visibility::Hidden;
+    }
+}
@@ -1,16 +1,88 @@
 use bevy::prelude::*;
 
-use super::{bundles::button, systems::button_system};
+use crate::{cube::ShapePalette, joint::JointSelection, terrain::tile_editor_plugin::TileEditor};
+
+use super::{
+    bundles::button,
+    components::{
+        ClickModeButton, GoalMessage, JointKindButton, PlayerFormButton, ShapePaletteButton,
+        TileMaterialButton,
+    },
+    systems::{
+        button_system, click_mode_button, joint_kind_button, on_player_form_changed,
+        player_form_button, shape_palette_button, show_goal_message, tile_material_button,
+        update_click_mode_text,
+    },
+};
 
 pub struct MyUiPlugin;
 
 impl Plugin for MyUiPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, setup)
-            .add_systems(Update, button_system);
+            .add_systems(
+                Update,
+                (
+                    button_system,
+                    click_mode_button,
+                    update_click_mode_text,
+                    joint_kind_button,
+                    shape_palette_button,
+                    player_form_button,
+                    tile_material_button,
+                    show_goal_message,
+                ),
+            )
+            .add_observer(on_player_form_changed);
     }
 }
 
-fn setup(mut commands: Commands, assets: Res<AssetServer>) {
-    commands.spawn(button("SpawnCube".to_string(), &assets));
+fn setup(
+    mut commands: Commands,
+    assets: Res<AssetServer>,
+    joint_selection: Res<JointSelection>,
+    palette: Res<ShapePalette>,
+    tile_editor: Res<TileEditor>,
+) {
+    commands
+        .spawn(Node {
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(5.0),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((button("SpawnCube".to_string(), &assets), ClickModeButton));
+            parent.spawn((
+                button(format!("Joint: {}", joint_selection.kind().name()), &assets),
+                JointKindButton,
+            ));
+            parent.spawn((
+                button(format!("Shape: {}", palette.name()), &assets),
+                ShapePaletteButton,
+            ));
+            parent.spawn((button("Form".to_string(), &assets), PlayerFormButton));
+            parent.spawn((
+                button(format!("Tile: {}", tile_editor.material.name()), &assets),
+                TileMaterialButton,
+            ));
+        });
+
+    commands.spawn((
+        GoalMessage(Timer::from_seconds(3.0, TimerMode::Once)),
+        Text::new("Goal reached!"),
+        TextFont {
+            font: assets.load("fonts/MonofurNerdFont-Regular.ttf"),
+            font_size: 60.0,
+            ..default()
+        },
+        TextColor(Color::srgb(1.0, 0.85, 0.2)),
+        TextShadow::default(),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(20.0),
+            justify_self: JustifySelf::Center,
+            ..default()
+        },
+        Visibility::Hidden,
+    ));
 }